@@ -0,0 +1,127 @@
+use raylib::prelude::*;
+
+/// time constant of the transitions of the camera, in seconds
+const TRANSITION_TIME: f32 = 0.15;
+/// the zoom stays between these multiples of the zoom fitting the playfield
+const MIN_ZOOM_FACTOR: f32 = 0.25;
+const MAX_ZOOM_FACTOR: f32 = 50.0;
+
+/// What the camera shows
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum CameraMode {
+    /// where the user panned and zoomed
+    Free,
+    /// the whole playfield, whatever the size of the window
+    Fit,
+    /// the centroid of the flock, at the current zoom
+    Centroid,
+    /// the selected boid, at the current zoom
+    Selected,
+}
+
+impl CameraMode {
+    fn is_following(self) -> bool {
+        self == CameraMode::Centroid || self == CameraMode::Selected
+    }
+}
+
+/// Camera of the application. The zoom and the pans of the user apply at once, the changes of
+/// mode are smoothed and the followed points are kept at the same place of the screen.
+pub struct CameraController {
+    mode: CameraMode,
+    camera: Camera2D,
+    /// point of the world at the offset of the camera and zoom the camera moves to
+    goal_target: Vector2,
+    goal_zoom: f32,
+    /// target of the camera minus the goal target, closing smoothly. `None` after a change
+    /// of mode, until the goal of the new mode is known.
+    gap: Option<Vector2>,
+}
+
+impl CameraController {
+    /// A camera fitting the playfield, `fit_zoom` being the zoom showing the whole playfield
+    pub fn new(offset: Vector2, fit_zoom: f32) -> Self {
+        CameraController {
+            mode: CameraMode::Fit,
+            camera: Camera2D { target: Vector2::zero(), offset, rotation: 0.0, zoom: fit_zoom },
+            goal_target: Vector2::zero(),
+            goal_zoom: fit_zoom,
+            gap: None,
+        }
+    }
+
+    pub fn camera(&self) -> Camera2D {
+        self.camera
+    }
+
+    pub fn mode(&self) -> CameraMode {
+        self.mode
+    }
+
+    /// The camera moves smoothly from its current state to the one of `mode`,
+    /// staying where it is for [`CameraMode::Free`]
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        if mode == CameraMode::Free {
+            self.goal_target = self.camera.target;
+        }
+        self.mode = mode;
+        self.gap = None;
+    }
+
+    /// Multiplies the zoom by `factor`, keeping still the point of the world under
+    /// `screen_position`, or the followed point when following one
+    pub fn zoom_at(&mut self, screen_position: Vector2, factor: f32, fit_zoom: f32) {
+        let zoom = (self.camera.zoom * factor).clamp(fit_zoom * MIN_ZOOM_FACTOR, fit_zoom * MAX_ZOOM_FACTOR);
+        if !self.mode.is_following() {
+            let anchor = self.to_world(screen_position);
+            self.camera.target = Vector2 {
+                x: anchor.x - (screen_position.x - self.camera.offset.x) / zoom,
+                y: anchor.y - (screen_position.y - self.camera.offset.y) / zoom,
+            };
+            self.goal_target = self.camera.target;
+            self.gap = Some(Vector2::zero());
+            self.mode = CameraMode::Free;
+        }
+        self.camera.zoom = zoom;
+        self.goal_zoom = zoom;
+    }
+
+    /// Moves the world by `delta` pixels on the screen
+    pub fn pan(&mut self, delta: Vector2) {
+        self.camera.target.x -= delta.x / self.camera.zoom;
+        self.camera.target.y -= delta.y / self.camera.zoom;
+        self.goal_target = self.camera.target;
+        self.goal_zoom = self.camera.zoom;
+        self.gap = Some(Vector2::zero());
+        self.mode = CameraMode::Free;
+    }
+
+    /// To be called every frame. `offset` is the point of the screen showing the target,
+    /// `followed` the point followed in the current mode. The camera is left free when
+    /// there is no point to follow.
+    pub fn update(&mut self, dt: f32, offset: Vector2, fit_zoom: f32, followed: Option<Vector2>) {
+        self.camera.offset = offset;
+        match (self.mode, followed) {
+            (CameraMode::Free, _) => {}
+            (CameraMode::Fit, _) => {
+                self.goal_target = Vector2::zero();
+                self.goal_zoom = fit_zoom;
+            }
+            (_, Some(point)) => self.goal_target = point,
+            (_, None) => self.set_mode(CameraMode::Free),
+        }
+        let t = 1.0 - (-dt / TRANSITION_TIME).exp();
+        let gap = self.gap.unwrap_or(self.camera.target - self.goal_target) * (1.0 - t);
+        self.gap = Some(gap);
+        self.camera.target = self.goal_target + gap;
+        // the zoom is interpolated geometrically, the scale changing at a steady rate
+        self.camera.zoom *= (self.goal_zoom / self.camera.zoom).powf(t);
+    }
+
+    fn to_world(&self, screen_position: Vector2) -> Vector2 {
+        Vector2 {
+            x: self.camera.target.x + (screen_position.x - self.camera.offset.x) / self.camera.zoom,
+            y: self.camera.target.y + (screen_position.y - self.camera.offset.y) / self.camera.zoom,
+        }
+    }
+}
@@ -0,0 +1,52 @@
+/// the speeds the simulation runs at relative to the wall clock
+const TIME_SCALES: [f32; 7] = [0.1, 0.2, 0.5, 1.0, 2.0, 5.0, 10.0];
+const DEFAULT_TIME_SCALE_INDEX: usize = 3;
+/// duration of the steps computed one at a time, the one of a frame at the target frame rate
+const SINGLE_STEP_DURATION: f32 = 1.0 / 60.0;
+
+/// Pause and speed of the simulation in the application
+pub struct SimulationClock {
+    pub paused: bool,
+    time_scale_index: usize,
+}
+
+impl SimulationClock {
+    pub fn new() -> Self {
+        SimulationClock {
+            paused: false,
+            time_scale_index: DEFAULT_TIME_SCALE_INDEX,
+        }
+    }
+
+    /// speed of the simulation relative to the wall clock
+    pub fn time_scale(&self) -> f32 {
+        TIME_SCALES[self.time_scale_index]
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Moves the speed `change` notches up or down, staying between the slowest and the fastest
+    pub fn change_time_scale(&mut self, change: i32) {
+        let index = self.time_scale_index as i32 + change;
+        self.time_scale_index = index.clamp(0, TIME_SCALES.len() as i32 - 1) as usize;
+    }
+
+    /// Number of steps to compute for a frame lasting `dt` seconds and their duration.
+    /// `single_step` pauses the simulation and computes one step.
+    pub fn frame_steps(&mut self, dt: f32, single_step: bool) -> (usize, f32) {
+        if single_step {
+            self.paused = true;
+        }
+        let time_scale = self.time_scale();
+        if self.paused {
+            (single_step as usize, SINGLE_STEP_DURATION * time_scale)
+        } else {
+            // the faster speeds are reached with several steps per frame, no step being
+            // longer than at normal speed
+            let nb_steps = time_scale.ceil() as usize;
+            (nb_steps, dt * time_scale / nb_steps as f32)
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use std::f32::consts::PI;
+use rand::Rng;
 
 use crate::data::vector::Vector;
 
@@ -18,6 +18,14 @@ impl Boid {
         }
     }
 
+    pub fn from_parts(position: Vector, velocity: Vector, speed: f32) -> Self {
+        Boid {
+            position,
+            velocity,
+            speed,
+        }
+    }
+
     pub fn update_speed(&mut self) {
         self.speed = self.velocity.x.hypot(self.velocity.y);
     }
@@ -26,15 +34,14 @@ impl Boid {
         self.position.add_scaled(&self.velocity, dt);
     }
 
-    pub fn clamp_speed(&mut self, min_speed: f32, max_speed: f32) {
+    pub fn clamp_speed(&mut self, min_speed: f32, max_speed: f32, rng: &mut impl Rng) {
         if self.speed > max_speed {
             self.velocity.scale(max_speed / self.speed);
             self.speed = max_speed;
         }
 
         if self.speed <= 1e-6 {
-            let v: f32 = rand::random::<f32>() * PI * 2.0;
-            self.velocity.set_random(min_speed);
+            self.velocity.set_random(min_speed, rng);
             self.speed = min_speed;
         } else if self.speed < min_speed {
             self.velocity.scale(min_speed / self.speed);
@@ -45,4 +52,18 @@ impl Boid {
         self.speed
     }
 
+    /// head, left wing and right wing of the triangle drawn for the boid,
+    /// pointing in the direction of its velocity
+    pub fn triangle(&self, bird_size: f32) -> [Vector; 3] {
+        let size_factor: f32 = 1.2;
+
+        let nvx = size_factor * bird_size * self.velocity.x / self.speed;
+        let nvy = size_factor * bird_size * self.velocity.y / self.speed;
+        [
+            Vector { x: nvx + self.position.x, y: nvy + self.position.y },
+            Vector { x: nvy * 0.3 + self.position.x, y: -nvx * 0.3 + self.position.y },
+            Vector { x: -nvy * 0.3 + self.position.x, y: nvx * 0.3 + self.position.y },
+        ]
+    }
+
 }
@@ -0,0 +1,60 @@
+use crate::data::flock::Flock;
+use crate::data::grid::CellGrid;
+
+/// Separates every pair of overlapping boids, each boid being a disk of radius `radius`.
+/// Both boids are moved apart by half the overlap and the approaching part of their
+/// relative velocity is cancelled. The indices of the colliding pairs are appended to `collisions`.
+/// The pairs are taken from `grid`, built again from the positions at the start of the pass.
+pub fn resolve_collisions(
+    flock: &mut Flock,
+    radius: f32,
+    grid: &mut CellGrid,
+    collisions: &mut Vec<(usize, usize)>,
+) {
+    let contact_distance = 2.0 * radius;
+    let nb_birds = flock.len();
+    grid.build(flock, contact_distance);
+
+    for i in 0..nb_birds {
+        let (x, y) = (flock.x[i], flock.y[i]);
+        grid.for_each_candidate(x, y, |j| {
+            if j <= i {
+                return;
+            }
+            let dx = flock.x[j] - flock.x[i];
+            let dy = flock.y[j] - flock.y[i];
+            if dx.abs() >= contact_distance || dy.abs() >= contact_distance {
+                return;
+            }
+            let distance = dx.hypot(dy);
+            if distance >= contact_distance {
+                return;
+            }
+
+            let (nx, ny) = if distance > 1e-6 {
+                (dx / distance, dy / distance)
+            } else {
+                (1.0, 0.0)
+            };
+
+            let correction = (contact_distance - distance) * 0.5;
+            flock.x[i] -= nx * correction;
+            flock.y[i] -= ny * correction;
+            flock.x[j] += nx * correction;
+            flock.y[j] += ny * correction;
+
+            let approach = (flock.vx[j] - flock.vx[i]) * nx + (flock.vy[j] - flock.vy[i]) * ny;
+            if approach < 0.0 {
+                let impulse = approach * 0.5;
+                flock.vx[i] += nx * impulse;
+                flock.vy[i] += ny * impulse;
+                flock.vx[j] -= nx * impulse;
+                flock.vy[j] -= ny * impulse;
+                flock.speed[i] = flock.vx[i].hypot(flock.vy[i]);
+                flock.speed[j] = flock.vx[j].hypot(flock.vy[j]);
+            }
+
+            collisions.push((i, j));
+        });
+    }
+}
@@ -0,0 +1,101 @@
+use std::fs;
+
+use crate::data::world::World;
+
+/// half size of the grids covering a playfield, relative to the half size of the playfield,
+/// the boids overshooting the boundary they are steered back into
+const PLAYFIELD_MARGIN: f32 = 1.25;
+
+/// Occupancy of the playfield accumulated over time: a grid of `resolution` x `resolution`
+/// cells covering the square of half size `half_size` around the origin, each cell counting
+/// the boids seen in it at every step. The counts decay with a half-life, so that the grid
+/// shows where the boids spent the last moments rather than the whole run.
+pub struct DensityGrid {
+    resolution: usize,
+    half_size: f32,
+    /// in seconds, 0 for counts that never decay
+    half_life: f32,
+    /// row major, the first row being the one of the smallest `y`
+    cells: Vec<f32>,
+    last_time: f32,
+}
+
+impl DensityGrid {
+    pub fn new(resolution: usize, half_size: f32, half_life: f32) -> Self {
+        let resolution = resolution.max(1);
+        DensityGrid {
+            resolution,
+            half_size,
+            half_life: half_life.max(0.0),
+            cells: vec![0.0; resolution * resolution],
+            last_time: 0.0,
+        }
+    }
+
+    /// A grid covering the playfield of half size `playfield_size` and its margins
+    pub fn for_playfield(resolution: usize, playfield_size: f32, half_life: f32) -> Self {
+        DensityGrid::new(resolution, playfield_size * PLAYFIELD_MARGIN, half_life)
+    }
+
+    pub fn resolution(&self) -> usize {
+        self.resolution
+    }
+
+    pub fn half_size(&self) -> f32 {
+        self.half_size
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        2.0 * self.half_size / self.resolution as f32
+    }
+
+    pub fn value(&self, column: usize, row: usize) -> f32 {
+        self.cells[row * self.resolution + column]
+    }
+
+    pub fn max(&self) -> f32 {
+        self.cells.iter().cloned().fold(0.0, f32::max)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.iter_mut().for_each(|cell| *cell = 0.0);
+    }
+
+    /// To be called after each step of duration `dt`. The grid is cleared when the
+    /// simulation time goes back, after a reset.
+    pub fn update(&mut self, world: &World, dt: f32) {
+        if world.time < self.last_time {
+            self.clear();
+        }
+        self.last_time = world.time;
+
+        if self.half_life > 0.0 {
+            let decay = 0.5_f32.powf(dt / self.half_life);
+            self.cells.iter_mut().for_each(|cell| *cell *= decay);
+        }
+        let scale = self.resolution as f32 / (2.0 * self.half_size);
+        for (x, y) in world.current.x.iter().zip(world.current.y.iter()) {
+            let column = ((x + self.half_size) * scale).floor();
+            let row = ((y + self.half_size) * scale).floor();
+            // the boids out of the grid are not counted
+            if column >= 0.0 && row >= 0.0 && (column as usize) < self.resolution && (row as usize) < self.resolution {
+                self.cells[row as usize * self.resolution + column as usize] += 1.0;
+            }
+        }
+    }
+
+    /// Writes the grid as CSV, one line per row of cells from the smallest `y`,
+    /// after a comment line giving the extent of the grid
+    pub fn save_csv(&self, path: &str) -> Result<(), String> {
+        let mut content = format!(
+            "# {0}x{0} cells covering [-{1}, {1}] x [-{1}, {1}], first row at y = -{1}\n",
+            self.resolution, self.half_size
+        );
+        for row in self.cells.chunks(self.resolution) {
+            let values: Vec<String> = row.iter().map(|value| value.to_string()).collect();
+            content.push_str(&values.join(","));
+            content.push('\n');
+        }
+        fs::write(path, content).map_err(|e| format!("Cannot write '{}': {}", path, e))
+    }
+}
@@ -0,0 +1,52 @@
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Side {
+    Left,
+    Right,
+    Bottom,
+    Top,
+}
+
+impl Side {
+    pub const ALL: [Side; 4] = [Side::Left, Side::Right, Side::Bottom, Side::Top];
+}
+
+#[derive(Clone, Debug)]
+pub enum SimulationEvent {
+    /// `first` sees `second` closer than the bird size. A pair can be
+    /// reported twice in the same step, once from each side.
+    NearCollision { first: usize, second: usize, distance: f32 },
+    /// the two boids overlapped and were separated by the collision pass
+    Collision { first: usize, second: usize },
+    /// the boid entered the constraint zone along the given side of the playfield
+    BoundaryContact { boid: usize, side: Side },
+    Spawned { boid: usize },
+    /// the boid at the given index was removed. Indices of the remaining boids are unchanged
+    Despawned { boid: usize },
+    PredatorReleased { predator: usize },
+    /// the predator reached the boid. The boid is not removed
+    PredatorCatch { predator: usize, boid: usize },
+    /// a parameter change of the scenario was rejected
+    ScenarioError { message: String },
+    /// the steering script failed and was removed
+    ScriptError { message: String },
+}
+
+pub trait SimulationObserver {
+    fn on_event(&mut self, event: &SimulationEvent);
+}
+
+impl<F> SimulationObserver for F where F: FnMut(&SimulationEvent) {
+    fn on_event(&mut self, event: &SimulationEvent) {
+        self(event)
+    }
+}
+
+/// Prints the errors of the scenario and of the script, to be registered as an observer
+/// by the applications running a world
+pub fn report_simulation_error(event: &SimulationEvent) {
+    match event {
+        SimulationEvent::ScenarioError { message } => eprintln!("scenario: {}", message),
+        SimulationEvent::ScriptError { message } => eprintln!("script: {}", message),
+        _ => {}
+    }
+}
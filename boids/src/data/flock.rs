@@ -0,0 +1,104 @@
+use crate::data::boid::Boid;
+use crate::data::vector::Vector;
+
+/// Struct-of-arrays storage of the boids. Each component lives in its own
+/// contiguous array so that the inner loops over the whole flock can be vectorised.
+#[derive(Clone, Default)]
+pub struct Flock {
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub vx: Vec<f32>,
+    pub vy: Vec<f32>,
+    pub speed: Vec<f32>,
+}
+
+impl Flock {
+    pub fn new(nb_birds: usize) -> Self {
+        Flock {
+            x: vec![0.0; nb_birds],
+            y: vec![0.0; nb_birds],
+            vx: vec![0.0; nb_birds],
+            vy: vec![0.0; nb_birds],
+            speed: vec![0.0; nb_birds],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    pub fn position(&self, index: usize) -> Vector {
+        Vector { x: self.x[index], y: self.y[index] }
+    }
+
+    pub fn velocity(&self, index: usize) -> Vector {
+        Vector { x: self.vx[index], y: self.vy[index] }
+    }
+
+    /// a copy of the boid at the given index
+    pub fn boid(&self, index: usize) -> Boid {
+        Boid::from_parts(self.position(index), self.velocity(index), self.speed[index])
+    }
+
+    pub fn set_boid(&mut self, index: usize, boid: &Boid) {
+        self.x[index] = boid.position.x;
+        self.y[index] = boid.position.y;
+        self.vx[index] = boid.velocity.x;
+        self.vy[index] = boid.velocity.y;
+        self.speed[index] = boid.speed();
+    }
+
+    pub fn push(&mut self, boid: &Boid) {
+        self.x.push(boid.position.x);
+        self.y.push(boid.position.y);
+        self.vx.push(boid.velocity.x);
+        self.vy.push(boid.velocity.y);
+        self.speed.push(boid.speed());
+    }
+
+    pub fn pop(&mut self) -> Option<Boid> {
+        if self.is_empty() {
+            return None;
+        }
+        let boid = self.boid(self.len() - 1);
+        self.x.pop();
+        self.y.pop();
+        self.vx.pop();
+        self.vy.pop();
+        self.speed.pop();
+        Some(boid)
+    }
+
+    /// mean position of the boids, `None` for an empty flock
+    pub fn centroid(&self) -> Option<Vector> {
+        if self.is_empty() {
+            return None;
+        }
+        let inv_nb_birds = 1.0 / self.len() as f32;
+        Some(Vector {
+            x: self.x.iter().sum::<f32>() * inv_nb_birds,
+            y: self.y.iter().sum::<f32>() * inv_nb_birds,
+        })
+    }
+
+    /// index of the boid closest to `position` with its distance, `None` for an empty flock
+    pub fn nearest(&self, position: &Vector) -> Option<(usize, f32)> {
+        let (px, py) = (position.x, position.y);
+        let (index, distance2) = self
+            .x
+            .iter()
+            .zip(self.y.iter())
+            .map(|(x, y)| (x - px) * (x - px) + (y - py) * (y - py))
+            .enumerate()
+            .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))?;
+        Some((index, distance2.sqrt()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Boid> + '_ {
+        (0..self.len()).map(move |index| self.boid(index))
+    }
+}
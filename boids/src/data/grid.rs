@@ -0,0 +1,128 @@
+use crate::data::flock::Flock;
+
+/// the grid has at most this number of columns and of rows, the cells being enlarged beyond
+const MAX_CELLS_PER_SIDE: usize = 1024;
+
+/// Uniform grid of square cells over the bounding box of the flock, the boids being sorted
+/// by cell. With cells at least as large as a radius, the boids closer than this radius to
+/// a point are in the cell of the point or in the eight cells around it.
+#[derive(Default)]
+pub struct CellGrid {
+    cell_size: f32,
+    min_x: f32,
+    min_y: f32,
+    nb_columns: usize,
+    nb_rows: usize,
+    /// the boids of cell `c` are `indices[cell_start[c]..cell_start[c + 1]]`
+    cell_start: Vec<usize>,
+    indices: Vec<usize>,
+    /// cell of each boid
+    cells: Vec<usize>,
+}
+
+impl CellGrid {
+    pub fn new() -> Self {
+        CellGrid::default()
+    }
+
+    /// Sorts the boids of the flock in cells of at least `min_cell_size`
+    pub fn build(&mut self, flock: &Flock, min_cell_size: f32) {
+        let fold = |values: &[f32]| {
+            values.iter().fold((f32::MAX, f32::MIN), |(min, max), value| (min.min(*value), max.max(*value)))
+        };
+        let (min_x, max_x) = if flock.is_empty() { (0.0, 0.0) } else { fold(&flock.x) };
+        let (min_y, max_y) = if flock.is_empty() { (0.0, 0.0) } else { fold(&flock.y) };
+        let extent = (max_x - min_x).max(max_y - min_y);
+        self.cell_size = min_cell_size.max(extent / MAX_CELLS_PER_SIDE as f32).max(1e-6);
+        self.min_x = min_x;
+        self.min_y = min_y;
+        self.nb_columns = ((max_x - min_x) / self.cell_size) as usize + 1;
+        self.nb_rows = ((max_y - min_y) / self.cell_size) as usize + 1;
+
+        // counting sort of the boids by cell
+        let nb_cells = self.nb_columns * self.nb_rows;
+        self.cell_start.clear();
+        self.cell_start.resize(nb_cells + 1, 0);
+        self.cells.clear();
+        for (x, y) in flock.x.iter().zip(flock.y.iter()) {
+            let cell = self.row(*y) * self.nb_columns + self.column(*x);
+            self.cells.push(cell);
+            self.cell_start[cell + 1] += 1;
+        }
+        for cell in 0..nb_cells {
+            self.cell_start[cell + 1] += self.cell_start[cell];
+        }
+        self.indices.clear();
+        self.indices.resize(flock.len(), 0);
+        let mut next = self.cell_start.clone();
+        for (index, cell) in self.cells.iter().enumerate() {
+            self.indices[next[*cell]] = index;
+            next[*cell] += 1;
+        }
+    }
+
+    /// Calls `visit` with the index of every boid of the cell of `(x, y)` and of the cells
+    /// around it: all the boids closer than the cell size, and others
+    pub fn for_each_candidate(&self, x: f32, y: f32, mut visit: impl FnMut(usize)) {
+        let (column, row) = (self.column(x), self.row(y));
+        for row in row.saturating_sub(1)..=(row + 1).min(self.nb_rows - 1) {
+            let first_cell = row * self.nb_columns + column.saturating_sub(1);
+            let last_cell = row * self.nb_columns + (column + 1).min(self.nb_columns - 1);
+            // the cells of a row are contiguous, and so are their boids
+            for index in self.indices[self.cell_start[first_cell]..self.cell_start[last_cell + 1]].iter() {
+                visit(*index);
+            }
+        }
+    }
+
+    /// Distance from boid `index` of the flock the grid was built from to its nearest
+    /// neighbour, infinite for a lone boid. The cells are searched in rings of growing size
+    /// around the cell of the boid until the rings left are farther than the nearest one found.
+    pub fn nearest_distance(&self, flock: &Flock, index: usize) -> f32 {
+        let (x, y) = (flock.x[index], flock.y[index]);
+        let (column, row) = (self.column(x), self.row(y));
+        let mut min_distance2 = f32::INFINITY;
+        for ring in 0..self.nb_columns.max(self.nb_rows) {
+            // the boids of this ring are at least ring - 1 cells away
+            let reach = ring.saturating_sub(1) as f32 * self.cell_size;
+            if min_distance2 <= reach * reach {
+                break;
+            }
+            let mut visit = |cells: std::ops::Range<usize>| {
+                for other in self.indices[self.cell_start[cells.start]..self.cell_start[cells.end]].iter() {
+                    if *other != index {
+                        let (dx, dy) = (flock.x[*other] - x, flock.y[*other] - y);
+                        min_distance2 = min_distance2.min(dx * dx + dy * dy);
+                    }
+                }
+            };
+            let first_column = column.saturating_sub(ring);
+            let last_column = (column + ring).min(self.nb_columns - 1);
+            for ring_row in row.saturating_sub(ring)..=(row + ring).min(self.nb_rows - 1) {
+                let row_start = ring_row * self.nb_columns;
+                if ring_row + ring == row || ring_row == row + ring {
+                    // top and bottom sides of the ring, whole rows of contiguous cells
+                    visit(row_start + first_column..row_start + last_column + 1);
+                } else {
+                    // left and right sides of the ring, when they are in the grid
+                    if column >= ring {
+                        visit(row_start + column - ring..row_start + column - ring + 1);
+                    }
+                    if column + ring < self.nb_columns {
+                        visit(row_start + column + ring..row_start + column + ring + 1);
+                    }
+                }
+            }
+        }
+        min_distance2.sqrt()
+    }
+
+    /// column of `x`, the points out of the grid being in the border columns
+    fn column(&self, x: f32) -> usize {
+        (((x - self.min_x) / self.cell_size).max(0.0) as usize).min(self.nb_columns - 1)
+    }
+
+    fn row(&self, y: f32) -> usize {
+        (((y - self.min_y) / self.cell_size).max(0.0) as usize).min(self.nb_rows - 1)
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// How the contribution of a neighbour to a steering rule decreases with its distance.
+/// Every kernel weights a neighbour at distance 0 by 1.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Kernel {
+    /// every neighbour counts the same
+    Uniform,
+    /// decreases linearly down to 0 at the perception radius
+    Linear,
+    /// decreases as the inverse square of the distance beyond a tenth of the perception radius
+    InverseSquare,
+    /// gaussian whose standard deviation is half the perception radius
+    Gaussian,
+}
+
+const INVERSE_SQUARE_CORE_RATIO: f32 = 0.1;
+const GAUSSIAN_SIGMA_RATIO: f32 = 0.5;
+
+impl Kernel {
+    pub const ALL: [Kernel; 4] = [Kernel::Uniform, Kernel::Linear, Kernel::InverseSquare, Kernel::Gaussian];
+
+    pub fn weight(&self, distance: f32, radius: f32) -> f32 {
+        match self {
+            Kernel::Uniform => 1.0,
+            Kernel::Linear => (1.0 - distance / radius).max(0.0),
+            Kernel::InverseSquare => {
+                let core = radius * INVERSE_SQUARE_CORE_RATIO;
+                let ratio = core / distance.max(core);
+                ratio * ratio
+            }
+            Kernel::Gaussian => {
+                let ratio = distance / (radius * GAUSSIAN_SIGMA_RATIO);
+                (-0.5 * ratio * ratio).exp()
+            }
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kernel::Uniform => "uniform",
+            Kernel::Linear => "linear",
+            Kernel::InverseSquare => "inverse_square",
+            Kernel::Gaussian => "gaussian",
+        }
+    }
+
+    /// the kernel following this one in [`Kernel::ALL`], wrapping around
+    pub fn next(&self) -> Kernel {
+        let index = Kernel::ALL.iter().position(|k| k == self).unwrap_or(0);
+        Kernel::ALL[(index + 1) % Kernel::ALL.len()]
+    }
+}
+
+impl fmt::Display for Kernel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Kernel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Kernel::ALL
+            .iter()
+            .find(|kernel| kernel.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown kernel '{}'", s))
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::data::flock::Flock;
+use crate::data::grid::CellGrid;
+
+/// Summary statistics of the flock state
+#[derive(Copy, Clone, Default, Debug)]
+pub struct FlockMetrics {
+    /// norm of the mean heading, 1 when all boids fly in the same direction
+    pub polarization: f32,
+    /// mean distance from each boid to its nearest neighbour
+    pub nearest_neighbour_distance: f32,
+    /// normalised angular momentum around the flock centroid, 1 for a perfect mill
+    pub milling: f32,
+}
+
+impl FlockMetrics {
+    pub fn compute(flock: &Flock) -> Self {
+        let nb_birds = flock.len();
+        if nb_birds == 0 {
+            return FlockMetrics::default();
+        }
+
+        let mut heading_x = 0.0;
+        let mut heading_y = 0.0;
+        let mut centroid_x = 0.0;
+        let mut centroid_y = 0.0;
+        for i in 0..nb_birds {
+            let speed = flock.speed[i].max(1e-6);
+            heading_x += flock.vx[i] / speed;
+            heading_y += flock.vy[i] / speed;
+            centroid_x += flock.x[i];
+            centroid_y += flock.y[i];
+        }
+        let inv_nb_birds = 1.0 / (nb_birds as f32);
+        centroid_x *= inv_nb_birds;
+        centroid_y *= inv_nb_birds;
+
+        let mut angular_momentum = 0.0;
+        for i in 0..nb_birds {
+            let rx = flock.x[i] - centroid_x;
+            let ry = flock.y[i] - centroid_y;
+            let r = rx.hypot(ry);
+            if r > 1e-6 {
+                angular_momentum += (rx * flock.vy[i] - ry * flock.vx[i]) / (r * flock.speed[i].max(1e-6));
+            }
+        }
+
+        FlockMetrics {
+            polarization: heading_x.hypot(heading_y) * inv_nb_birds,
+            nearest_neighbour_distance: mean_nearest_neighbour_distance(flock),
+            milling: angular_momentum.abs() * inv_nb_birds,
+        }
+    }
+
+    pub fn add(&mut self, other: &FlockMetrics) {
+        self.polarization += other.polarization;
+        self.nearest_neighbour_distance += other.nearest_neighbour_distance;
+        self.milling += other.milling;
+    }
+
+    pub fn scale(&mut self, scale: f32) {
+        self.polarization *= scale;
+        self.nearest_neighbour_distance *= scale;
+        self.milling *= scale;
+    }
+}
+
+fn mean_nearest_neighbour_distance(flock: &Flock) -> f32 {
+    let nb_birds = flock.len();
+    if nb_birds < 2 {
+        return 0.0;
+    }
+
+    // cells holding about one boid each, the boids of a flock stretched along a line
+    // being spread along its length
+    let extent = |values: &[f32]| {
+        values.iter().cloned().fold(f32::NEG_INFINITY, f32::max) - values.iter().cloned().fold(f32::INFINITY, f32::min)
+    };
+    let (width, height) = (extent(&flock.x), extent(&flock.y));
+    let spacing = (width * height / nb_birds as f32).sqrt().max(width.max(height) / nb_birds as f32);
+    let mut grid = CellGrid::new();
+    grid.build(flock, spacing);
+
+    let sum: f32 = (0..nb_birds).map(|i| grid.nearest_distance(flock, i)).sum();
+    sum / (nb_birds as f32)
+}
+
+/// Quantity computed for each boid, used to colour them
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BoidMetric {
+    Speed,
+    /// direction of the velocity, in radians in `[-π, π]`
+    Heading,
+    /// number of other boids closer than the given radius
+    Neighbours,
+    /// rank of the cluster of the boid, the clusters being the groups of boids linked by
+    /// distances smaller than the given radius, from the largest (0). -1 for isolated boids.
+    Cluster,
+}
+
+impl BoidMetric {
+    pub const ALL: [BoidMetric; 4] =
+        [BoidMetric::Speed, BoidMetric::Heading, BoidMetric::Neighbours, BoidMetric::Cluster];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BoidMetric::Speed => "speed",
+            BoidMetric::Heading => "heading",
+            BoidMetric::Neighbours => "neighbours",
+            BoidMetric::Cluster => "cluster",
+        }
+    }
+
+    /// value of the metric for each boid of the flock, `radius` being the one of
+    /// [`BoidMetric::Neighbours`] and [`BoidMetric::Cluster`]
+    pub fn compute(&self, flock: &Flock, radius: f32) -> Vec<f32> {
+        match self {
+            BoidMetric::Speed => flock.speed.clone(),
+            BoidMetric::Heading => flock.vx.iter().zip(flock.vy.iter()).map(|(vx, vy)| vy.atan2(*vx)).collect(),
+            BoidMetric::Neighbours => {
+                let radius2 = radius * radius;
+                let mut grid = CellGrid::new();
+                grid.build(flock, radius);
+                (0..flock.len())
+                    .map(|i| {
+                        let (x, y) = (flock.x[i], flock.y[i]);
+                        let mut nb_close = 0;
+                        grid.for_each_candidate(x, y, |j| {
+                            let (dx, dy) = (x - flock.x[j], y - flock.y[j]);
+                            if j != i && dx * dx + dy * dy <= radius2 {
+                                nb_close += 1;
+                            }
+                        });
+                        nb_close as f32
+                    })
+                    .collect()
+            }
+            BoidMetric::Cluster => cluster_ranks(flock, radius),
+        }
+    }
+
+    /// next metric, `None` standing for no metric and coming after the last one
+    pub fn next(metric: Option<BoidMetric>) -> Option<BoidMetric> {
+        match metric {
+            None => Some(BoidMetric::ALL[0]),
+            Some(metric) => {
+                let index = BoidMetric::ALL.iter().position(|m| *m == metric).unwrap();
+                BoidMetric::ALL.get(index + 1).copied()
+            }
+        }
+    }
+}
+
+fn cluster_ranks(flock: &Flock, radius: f32) -> Vec<f32> {
+    let nb_birds = flock.len();
+    let radius2 = radius * radius;
+
+    // union-find of the boids closer than radius, each pair being linked once
+    let mut grid = CellGrid::new();
+    grid.build(flock, radius);
+    let mut parents: Vec<usize> = (0..nb_birds).collect();
+    for i in 0..nb_birds {
+        let (x, y) = (flock.x[i], flock.y[i]);
+        grid.for_each_candidate(x, y, |j| {
+            let (dx, dy) = (x - flock.x[j], y - flock.y[j]);
+            if j > i && dx * dx + dy * dy <= radius2 {
+                let (root_i, root_j) = (find_root(&mut parents, i), find_root(&mut parents, j));
+                parents[root_i] = root_j;
+            }
+        });
+    }
+
+    let roots: Vec<usize> = (0..nb_birds).map(|i| find_root(&mut parents, i)).collect();
+    let mut sizes = vec![0usize; nb_birds];
+    for root in roots.iter() {
+        sizes[*root] += 1;
+    }
+    let mut clusters: Vec<usize> = (0..nb_birds).filter(|i| sizes[*i] > 1).collect();
+    // largest first, the index of the root making the order stable
+    clusters.sort_by_key(|root| (std::cmp::Reverse(sizes[*root]), *root));
+    let mut ranks = vec![-1.0; nb_birds];
+    for (rank, root) in clusters.iter().enumerate() {
+        ranks[*root] = rank as f32;
+    }
+    roots.iter().map(|root| ranks[*root]).collect()
+}
+
+fn find_root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+impl fmt::Display for BoidMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for BoidMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BoidMetric::ALL
+            .iter()
+            .find(|metric| metric.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown metric '{}', expected speed, heading, neighbours or cluster", s))
+    }
+}
@@ -1,4 +1,22 @@
 pub mod boid;
+pub mod collision;
+pub mod density;
+pub mod event;
+pub mod flock;
+pub mod grid;
+pub mod kernel;
+pub mod metrics;
+pub mod neighbour;
+pub mod obstacle;
+pub mod occlusion;
+pub mod parameters;
+pub mod predator;
+pub mod preset;
+pub mod profile;
+pub mod quadtree;
+pub mod scenario;
+pub mod script;
 pub mod steering;
+pub mod trails;
 pub mod vector;
 pub mod world;
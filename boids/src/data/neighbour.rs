@@ -0,0 +1,26 @@
+use crate::data::vector::Vector;
+
+pub const IN_SEPARATION_RANGE: u8 = 1;
+pub const IN_ALIGNMENT_RANGE: u8 = 2;
+pub const IN_COHESION_RANGE: u8 = 4;
+
+#[derive(Copy, Clone)]
+pub struct Neighbour {
+    pub index: usize,
+    /// position of the reference boid minus the position of this neighbour
+    pub offset: Vector,
+    pub distance: f32,
+    /// combination of the `IN_*_RANGE` flags of the rules this neighbour is perceived by
+    pub visibility: u8,
+}
+
+impl Neighbour {
+    pub fn new(index: usize, offset: Vector, visibility: u8) -> Self {
+        Neighbour {
+            index,
+            offset,
+            distance: offset.hypot(),
+            visibility,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::data::boid::Boid;
+use crate::data::vector::Vector;
+
+/// distance from the obstacle border, in bird sizes, from which the boids start to avoid it
+const AVOIDANCE_RANGE_FACTOR: f32 = 5.0;
+const AVOIDANCE_STRENGTH: f32 = 2.0;
+
+/// A disc the boids cannot enter and steer around
+#[derive(Copy, Clone)]
+pub struct Obstacle {
+    pub center: Vector,
+    pub radius: f32,
+}
+
+impl Obstacle {
+    pub fn new(x: f32, y: f32, radius: f32) -> Self {
+        Obstacle {
+            center: Vector { x, y },
+            radius,
+        }
+    }
+
+    /// Steers the boid away from the obstacle when it comes close and
+    /// moves it back on the border if it ended up inside
+    pub fn avoid(&self, boid: &mut Boid, bird_size: f32) {
+        let mut offset = boid.position;
+        offset.subtract(&self.center);
+        let distance = offset.hypot();
+        let range = bird_size * AVOIDANCE_RANGE_FACTOR;
+        if distance >= self.radius + range || distance <= 1e-6 {
+            return;
+        }
+        offset.scale(1.0 / distance);
+
+        let strength = AVOIDANCE_STRENGTH * (self.radius + range - distance) / range;
+        boid.velocity.add_scaled(&offset, strength);
+
+        if distance < self.radius {
+            boid.position = self.center;
+            boid.position.add_scaled(&offset, self.radius);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::f32::consts::PI;
+
+use crate::data::neighbour::Neighbour;
+use crate::data::obstacle::Obstacle;
+use crate::data::vector::Vector;
+
+#[derive(Copy, Clone, Default)]
+pub struct PerceptionStats {
+    /// number of neighbours that were seen during the last step
+    pub nb_perceived: usize,
+    /// number of neighbours hidden behind closer boids during the last step
+    pub nb_occluded: usize,
+}
+
+/// Removes from `neighbours` the boids hidden behind a closer boid, i.e. whose
+/// direction falls inside the angular size of a closer boid as seen from the reference,
+/// and the boids whose line of sight from the reference at `position` crosses an obstacle.
+/// The reference itself (`reference_index`) is never occluded and never occludes.
+/// Returns the number of removed neighbours.
+pub fn remove_occluded(
+    reference_index: usize,
+    position: &Vector,
+    neighbours: &mut Vec<Neighbour>,
+    bird_size: f32,
+    obstacles: &[Obstacle],
+) -> usize {
+    neighbours.sort_by(|n1, n2| n1.distance.total_cmp(&n2.distance));
+
+    let radius = bird_size * 0.5;
+    let mut occluders: Vec<(f32, f32)> = Vec::with_capacity(neighbours.len());
+    let nb_neighbours = neighbours.len();
+
+    neighbours.retain(|neighbour| {
+        if neighbour.index == reference_index {
+            return true;
+        }
+        if obstacles.iter().any(|obstacle| hides(obstacle, position, &neighbour.offset)) {
+            return false;
+        }
+        let direction = (-neighbour.offset.y).atan2(-neighbour.offset.x);
+        let occluded = occluders
+            .iter()
+            .any(|(angle, half_width)| angle_between(*angle, direction) < *half_width);
+
+        let half_width = (radius / neighbour.distance.max(radius)).asin();
+        occluders.push((direction, half_width));
+        !occluded
+    });
+
+    nb_neighbours - neighbours.len()
+}
+
+/// whether `obstacle` crosses the segment from `position` to `position - offset`
+fn hides(obstacle: &Obstacle, position: &Vector, offset: &Vector) -> bool {
+    let (cx, cy) = (obstacle.center.x - position.x, obstacle.center.y - position.y);
+    let (dx, dy) = (-offset.x, -offset.y);
+    let length2 = dx * dx + dy * dy;
+    if length2 <= 0.0 {
+        return false;
+    }
+    let t = ((cx * dx + cy * dy) / length2).clamp(0.0, 1.0);
+    let (ex, ey) = (cx - t * dx, cy - t * dy);
+    ex * ex + ey * ey < obstacle.radius * obstacle.radius
+}
+
+fn angle_between(angle1: f32, angle2: f32) -> f32 {
+    let diff = (angle1 - angle2).abs() % (2.0 * PI);
+    if diff > PI { 2.0 * PI - diff } else { diff }
+}
@@ -0,0 +1,473 @@
+use std::fmt;
+
+use crate::data::kernel::Kernel;
+
+const SAFE_SPACE_RATIO: f32 = 0.8;
+const DEFAULT_VISIBILITY_FACTOR: f32 = 3.0;
+
+// in degree
+const DEAD_ANGLE: f32 = 20.0;
+const DEFAULT_SEPARATION_FACTOR: f32 = 6.0;
+const DEFAULT_COHESION_FACTOR: f32 = 4.0;
+const DEFAULT_ALIGNMENT_FACTOR: f32 = 10.;
+const DEFAULT_BARNES_HUT_THETA: f32 = 0.5;
+
+pub const DEFAULT_BIRD_SIZE: f32 = 0.2;
+pub const DEFAULT_BIRD_MIN_SPEED: f32 = 5.0;
+const DEFAULT_BIRD_MAX_SPEED: f32 = 16.0;
+
+/// What a boid perceives for one steering rule: the neighbours closer than
+/// `radius` and outside the dead angle behind it, weighted by `kernel`
+#[derive(Copy, Clone)]
+pub struct Perception {
+    radius: f32,
+    dead_angle: f32,
+    cos_max_angle: f32,
+    kernel: Kernel,
+}
+
+impl Perception {
+    fn new(radius: f32, dead_angle: f32, kernel: Kernel) -> Self {
+        Perception {
+            radius,
+            dead_angle,
+            cos_max_angle: compute_cos_max_angle(dead_angle),
+            kernel,
+        }
+    }
+
+    pub fn radius(&self) -> f32 {
+        self.radius
+    }
+
+    /// in degree
+    pub fn dead_angle(&self) -> f32 {
+        self.dead_angle
+    }
+
+    pub fn cos_max_angle(&self) -> f32 {
+        self.cos_max_angle
+    }
+
+    pub fn kernel(&self) -> Kernel {
+        self.kernel
+    }
+
+    /// weight of a neighbour at `distance` for this rule
+    pub fn weight(&self, distance: f32) -> f32 {
+        self.kernel.weight(distance, self.radius)
+    }
+}
+
+/// Parameters of the simulation. They can only be created through a
+/// [`ParametersBuilder`] that checks they are consistent.
+#[derive(Clone)]
+pub struct Parameters {
+    bird_size: f32,
+    min_bird_speed: f32,
+    max_bird_speed: f32,
+    separation: Perception,
+    alignment: Perception,
+    cohesion: Perception,
+    separation_factor: f32,
+    cohesion_factor: f32,
+    alignment_factor: f32,
+    long_range_factor: f32,
+    barnes_hut_theta: f32,
+    occlusion: bool,
+    collisions: bool,
+}
+
+pub fn compute_cos_max_angle(dead_angle: f32) -> f32 {
+    (std::f32::consts::PI * (1. - dead_angle / 180.)).cos()
+}
+
+impl Parameters {
+    pub fn new() -> Self {
+        Parameters {
+            bird_size: DEFAULT_BIRD_SIZE,
+            separation: Perception::new(
+                DEFAULT_BIRD_SIZE * DEFAULT_VISIBILITY_FACTOR * SAFE_SPACE_RATIO,
+                DEAD_ANGLE,
+                Kernel::Uniform,
+            ),
+            alignment: Perception::new(
+                DEFAULT_BIRD_SIZE * DEFAULT_VISIBILITY_FACTOR,
+                DEAD_ANGLE,
+                Kernel::Uniform,
+            ),
+            cohesion: Perception::new(
+                DEFAULT_BIRD_SIZE * DEFAULT_VISIBILITY_FACTOR,
+                DEAD_ANGLE,
+                Kernel::Uniform,
+            ),
+            cohesion_factor: DEFAULT_COHESION_FACTOR * 0.01,
+            separation_factor: DEFAULT_SEPARATION_FACTOR * 0.01,
+            alignment_factor: DEFAULT_ALIGNMENT_FACTOR * 0.01,
+            long_range_factor: 0.0,
+            barnes_hut_theta: DEFAULT_BARNES_HUT_THETA,
+            min_bird_speed: DEFAULT_BIRD_MIN_SPEED,
+            max_bird_speed: DEFAULT_BIRD_MAX_SPEED,
+            occlusion: false,
+            collisions: false,
+        }
+    }
+
+    /// a builder initialised with these parameters
+    pub fn to_builder(&self) -> ParametersBuilder {
+        ParametersBuilder {
+            bird_size: self.bird_size,
+            min_bird_speed: self.min_bird_speed,
+            max_bird_speed: self.max_bird_speed,
+            separation_radius: self.separation.radius,
+            alignment_radius: self.alignment.radius,
+            cohesion_radius: self.cohesion.radius,
+            separation_dead_angle: self.separation.dead_angle,
+            alignment_dead_angle: self.alignment.dead_angle,
+            cohesion_dead_angle: self.cohesion.dead_angle,
+            separation_kernel: self.separation.kernel,
+            alignment_kernel: self.alignment.kernel,
+            cohesion_kernel: self.cohesion.kernel,
+            separation_factor: self.separation_factor,
+            cohesion_factor: self.cohesion_factor,
+            alignment_factor: self.alignment_factor,
+            long_range_factor: self.long_range_factor,
+            barnes_hut_theta: self.barnes_hut_theta,
+            occlusion: self.occlusion,
+            collisions: self.collisions,
+        }
+    }
+
+    pub fn bird_size(&self) -> f32 {
+        self.bird_size
+    }
+
+    pub fn min_bird_speed(&self) -> f32 {
+        self.min_bird_speed
+    }
+
+    pub fn max_bird_speed(&self) -> f32 {
+        self.max_bird_speed
+    }
+
+    pub fn separation(&self) -> &Perception {
+        &self.separation
+    }
+
+    pub fn alignment(&self) -> &Perception {
+        &self.alignment
+    }
+
+    pub fn cohesion(&self) -> &Perception {
+        &self.cohesion
+    }
+
+    /// the largest radius of the three rules, no neighbour is perceived beyond it
+    pub fn max_radius(&self) -> f32 {
+        self.separation.radius.max(self.alignment.radius).max(self.cohesion.radius)
+    }
+
+    pub fn separation_factor(&self) -> f32 {
+        self.separation_factor
+    }
+
+    pub fn cohesion_factor(&self) -> f32 {
+        self.cohesion_factor
+    }
+
+    pub fn alignment_factor(&self) -> f32 {
+        self.alignment_factor
+    }
+
+    /// strength of the attraction every boid feels towards the whole flock, 0 disables it
+    pub fn long_range_factor(&self) -> f32 {
+        self.long_range_factor
+    }
+
+    /// accuracy of the long range attraction: a group of boids is seen as a single mass
+    /// when its size divided by its distance is below theta. 0 computes every pair.
+    pub fn barnes_hut_theta(&self) -> f32 {
+        self.barnes_hut_theta
+    }
+
+    /// when set, neighbours hidden behind closer boids or behind obstacles are not perceived
+    pub fn occlusion(&self) -> bool {
+        self.occlusion
+    }
+
+    /// when set, overlapping boids are pushed apart after each step, `bird_size` being their radius
+    pub fn collisions(&self) -> bool {
+        self.collisions
+    }
+}
+
+#[derive(Clone)]
+pub struct ParametersBuilder {
+    bird_size: f32,
+    min_bird_speed: f32,
+    max_bird_speed: f32,
+    separation_radius: f32,
+    alignment_radius: f32,
+    cohesion_radius: f32,
+    separation_dead_angle: f32,
+    alignment_dead_angle: f32,
+    cohesion_dead_angle: f32,
+    separation_kernel: Kernel,
+    alignment_kernel: Kernel,
+    cohesion_kernel: Kernel,
+    separation_factor: f32,
+    cohesion_factor: f32,
+    alignment_factor: f32,
+    long_range_factor: f32,
+    barnes_hut_theta: f32,
+    occlusion: bool,
+    collisions: bool,
+}
+
+impl ParametersBuilder {
+    /// a builder initialised with the default parameters
+    pub fn new() -> Self {
+        Parameters::new().to_builder()
+    }
+
+    pub fn set_bird_size(&mut self, bird_size: f32) -> &mut ParametersBuilder {
+        self.bird_size = bird_size;
+        self
+    }
+
+    pub fn set_min_bird_speed(&mut self, min_bird_speed: f32) -> &mut ParametersBuilder {
+        self.min_bird_speed = min_bird_speed;
+        self
+    }
+
+    pub fn set_max_bird_speed(&mut self, max_bird_speed: f32) -> &mut ParametersBuilder {
+        self.max_bird_speed = max_bird_speed;
+        self
+    }
+
+    pub fn set_separation_radius(&mut self, radius: f32) -> &mut ParametersBuilder {
+        self.separation_radius = radius;
+        self
+    }
+
+    pub fn set_alignment_radius(&mut self, radius: f32) -> &mut ParametersBuilder {
+        self.alignment_radius = radius;
+        self
+    }
+
+    pub fn set_cohesion_radius(&mut self, radius: f32) -> &mut ParametersBuilder {
+        self.cohesion_radius = radius;
+        self
+    }
+
+    /// in degree
+    pub fn set_separation_dead_angle(&mut self, dead_angle: f32) -> &mut ParametersBuilder {
+        self.separation_dead_angle = dead_angle;
+        self
+    }
+
+    /// in degree
+    pub fn set_alignment_dead_angle(&mut self, dead_angle: f32) -> &mut ParametersBuilder {
+        self.alignment_dead_angle = dead_angle;
+        self
+    }
+
+    /// in degree
+    pub fn set_cohesion_dead_angle(&mut self, dead_angle: f32) -> &mut ParametersBuilder {
+        self.cohesion_dead_angle = dead_angle;
+        self
+    }
+
+    /// sets the same dead angle for the three rules
+    pub fn set_dead_angle(&mut self, dead_angle: f32) -> &mut ParametersBuilder {
+        self.set_separation_dead_angle(dead_angle)
+            .set_alignment_dead_angle(dead_angle)
+            .set_cohesion_dead_angle(dead_angle)
+    }
+
+    pub fn set_separation_kernel(&mut self, kernel: Kernel) -> &mut ParametersBuilder {
+        self.separation_kernel = kernel;
+        self
+    }
+
+    pub fn set_alignment_kernel(&mut self, kernel: Kernel) -> &mut ParametersBuilder {
+        self.alignment_kernel = kernel;
+        self
+    }
+
+    pub fn set_cohesion_kernel(&mut self, kernel: Kernel) -> &mut ParametersBuilder {
+        self.cohesion_kernel = kernel;
+        self
+    }
+
+    pub fn set_separation_factor(&mut self, separation_factor: f32) -> &mut ParametersBuilder {
+        self.separation_factor = separation_factor;
+        self
+    }
+
+    pub fn set_cohesion_factor(&mut self, cohesion_factor: f32) -> &mut ParametersBuilder {
+        self.cohesion_factor = cohesion_factor;
+        self
+    }
+
+    pub fn set_alignment_factor(&mut self, alignment_factor: f32) -> &mut ParametersBuilder {
+        self.alignment_factor = alignment_factor;
+        self
+    }
+
+    pub fn set_long_range_factor(&mut self, long_range_factor: f32) -> &mut ParametersBuilder {
+        self.long_range_factor = long_range_factor;
+        self
+    }
+
+    pub fn set_barnes_hut_theta(&mut self, barnes_hut_theta: f32) -> &mut ParametersBuilder {
+        self.barnes_hut_theta = barnes_hut_theta;
+        self
+    }
+
+    pub fn set_occlusion(&mut self, occlusion: bool) -> &mut ParametersBuilder {
+        self.occlusion = occlusion;
+        self
+    }
+
+    pub fn set_collisions(&mut self, collisions: bool) -> &mut ParametersBuilder {
+        self.collisions = collisions;
+        self
+    }
+
+    /// Checks every field and returns the parameters, or all the violations found
+    pub fn build(&self) -> Result<Parameters, ParametersError> {
+        let mut violations = Vec::new();
+
+        let positive_fields = [
+            ("bird_size", self.bird_size),
+            ("min_bird_speed", self.min_bird_speed),
+            ("max_bird_speed", self.max_bird_speed),
+            ("separation_radius", self.separation_radius),
+            ("alignment_radius", self.alignment_radius),
+            ("cohesion_radius", self.cohesion_radius),
+        ];
+        let non_negative_fields = [
+            ("separation_factor", self.separation_factor),
+            ("cohesion_factor", self.cohesion_factor),
+            ("alignment_factor", self.alignment_factor),
+            ("long_range_factor", self.long_range_factor),
+        ];
+        let bounded_fields = [
+            ("separation_dead_angle", self.separation_dead_angle, 0.0, 180.0),
+            ("alignment_dead_angle", self.alignment_dead_angle, 0.0, 180.0),
+            ("cohesion_dead_angle", self.cohesion_dead_angle, 0.0, 180.0),
+            ("barnes_hut_theta", self.barnes_hut_theta, 0.0, 2.0),
+        ];
+
+        for (field, value) in positive_fields.iter() {
+            if !value.is_finite() {
+                violations.push(ParameterViolation::NotFinite { field, value: *value });
+            } else if *value <= 0.0 {
+                violations.push(ParameterViolation::NotPositive { field, value: *value });
+            }
+        }
+        for (field, value) in non_negative_fields.iter() {
+            if !value.is_finite() {
+                violations.push(ParameterViolation::NotFinite { field, value: *value });
+            } else if *value < 0.0 {
+                violations.push(ParameterViolation::Negative { field, value: *value });
+            }
+        }
+        for (field, value, min, max) in bounded_fields.iter() {
+            if !value.is_finite() {
+                violations.push(ParameterViolation::NotFinite { field, value: *value });
+            } else if value < min || value > max {
+                violations.push(ParameterViolation::OutOfRange { field, value: *value, min: *min, max: *max });
+            }
+        }
+
+        if self.min_bird_speed > self.max_bird_speed {
+            violations.push(ParameterViolation::MinSpeedAboveMaxSpeed {
+                min_bird_speed: self.min_bird_speed,
+                max_bird_speed: self.max_bird_speed,
+            });
+        }
+
+        if !violations.is_empty() {
+            return Err(ParametersError { violations });
+        }
+
+        Ok(Parameters {
+            bird_size: self.bird_size,
+            min_bird_speed: self.min_bird_speed,
+            max_bird_speed: self.max_bird_speed,
+            separation: Perception::new(
+                self.separation_radius,
+                self.separation_dead_angle,
+                self.separation_kernel,
+            ),
+            alignment: Perception::new(
+                self.alignment_radius,
+                self.alignment_dead_angle,
+                self.alignment_kernel,
+            ),
+            cohesion: Perception::new(self.cohesion_radius, self.cohesion_dead_angle, self.cohesion_kernel),
+            separation_factor: self.separation_factor,
+            cohesion_factor: self.cohesion_factor,
+            alignment_factor: self.alignment_factor,
+            long_range_factor: self.long_range_factor,
+            barnes_hut_theta: self.barnes_hut_theta,
+            occlusion: self.occlusion,
+            collisions: self.collisions,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterViolation {
+    NotFinite { field: &'static str, value: f32 },
+    NotPositive { field: &'static str, value: f32 },
+    Negative { field: &'static str, value: f32 },
+    OutOfRange { field: &'static str, value: f32, min: f32, max: f32 },
+    MinSpeedAboveMaxSpeed { min_bird_speed: f32, max_bird_speed: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParametersError {
+    pub violations: Vec<ParameterViolation>,
+}
+
+impl fmt::Display for ParameterViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParameterViolation::NotFinite { field, value } => {
+                write!(f, "{} must be finite but is {}", field, value)
+            }
+            ParameterViolation::NotPositive { field, value } => {
+                write!(f, "{} must be positive but is {}", field, value)
+            }
+            ParameterViolation::Negative { field, value } => {
+                write!(f, "{} must not be negative but is {}", field, value)
+            }
+            ParameterViolation::OutOfRange { field, value, min, max } => {
+                write!(f, "{} must be between {} and {} but is {}", field, min, max, value)
+            }
+            ParameterViolation::MinSpeedAboveMaxSpeed { min_bird_speed, max_bird_speed } => write!(
+                f,
+                "min_bird_speed ({}) must not exceed max_bird_speed ({})",
+                min_bird_speed, max_bird_speed
+            ),
+        }
+    }
+}
+
+impl fmt::Display for ParametersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid parameters: ")?;
+        for (index, violation) in self.violations.iter().enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParametersError {}
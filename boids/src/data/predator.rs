@@ -0,0 +1,40 @@
+use crate::data::boid::Boid;
+use crate::data::flock::Flock;
+use crate::data::vector::Vector;
+
+/// constant speed of the predators
+pub const PREDATOR_SPEED: f32 = 12.0;
+/// boids closer than this to a predator flee from it
+pub const FLEE_RADIUS: f32 = 2.0;
+const FLEE_STRENGTH: f32 = 3.0;
+const CHASE_STRENGTH: f32 = 0.3;
+
+/// Predators are moved like boids but steer towards the closest boid instead of flocking
+pub fn create_predator(x: f32, y: f32) -> Boid {
+    let mut predator = Boid::from_parts(Vector { x, y }, Vector { x: PREDATOR_SPEED, y: 0.0 }, 0.0);
+    predator.update_speed();
+    predator
+}
+
+/// Steers the predator towards the closest boid of the flock and
+/// returns the index of this boid with its distance
+pub fn chase(predator: &mut Boid, flock: &Flock) -> Option<(usize, f32)> {
+    let (index, distance) = flock.nearest(&predator.position)?;
+
+    let mut direction = flock.position(index);
+    direction.subtract(&predator.position);
+    predator.velocity.add_scaled(&direction, CHASE_STRENGTH * PREDATOR_SPEED / distance.max(1e-3));
+    predator.update_speed();
+    Some((index, distance))
+}
+
+/// Steers the boid away from the predator if it is closer than [`FLEE_RADIUS`]
+pub fn flee(boid: &mut Boid, predator: &Boid) {
+    let mut offset = boid.position;
+    offset.subtract(&predator.position);
+    let distance = offset.hypot();
+    if distance >= FLEE_RADIUS || distance <= 1e-6 {
+        return;
+    }
+    boid.velocity.add_scaled(&offset, FLEE_STRENGTH * (FLEE_RADIUS - distance) / (FLEE_RADIUS * distance));
+}
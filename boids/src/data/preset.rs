@@ -0,0 +1,132 @@
+use std::fs;
+
+use crate::data::kernel::Kernel;
+use crate::data::parameters::{Parameters, ParametersBuilder};
+
+/// Writes the parameters as a preset: one `name = value` line per field.
+/// `comments` are written first, each line prefixed by `#`.
+pub fn save_preset(parameters: &Parameters, path: &str, comments: &[String]) -> Result<(), String> {
+    let mut content = String::new();
+    for comment in comments {
+        content.push_str(&format!("# {}\n", comment));
+    }
+    for (name, value) in preset_values(parameters).iter() {
+        content.push_str(&format!("{} = {}\n", name, value));
+    }
+    fs::write(path, content).map_err(|e| format!("Cannot write preset '{}': {}", path, e))
+}
+
+/// Reads a preset written by [`save_preset`]. Missing fields keep their default value.
+pub fn load_preset(path: &str) -> Result<Parameters, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Cannot read preset '{}': {}", path, e))?;
+    parse_preset(&content).map_err(|e| format!("Invalid preset '{}': {}", path, e))
+}
+
+pub fn parse_preset(content: &str) -> Result<Parameters, String> {
+    let mut builder = ParametersBuilder::new();
+    // the legacy `safe_space_ratio` is relative to the alignment radius, which may come later
+    let mut safe_space_ratio = None;
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("line {}: expected 'name = value'", line_number + 1))?;
+        let (name, value) = (name.trim(), value.trim());
+        if name == "safe_space_ratio" {
+            let ratio = value
+                .parse::<f32>()
+                .map_err(|_| format!("line {}: invalid number '{}' for '{}'", line_number + 1, value, name))?;
+            safe_space_ratio = Some(ratio);
+            continue;
+        }
+        set_preset_value(&mut builder, name, value).map_err(|e| format!("line {}: {}", line_number + 1, e))?;
+    }
+    let parameters = builder.build().map_err(|e| e.to_string())?;
+    match safe_space_ratio {
+        None => Ok(parameters),
+        Some(ratio) => parameters
+            .to_builder()
+            .set_separation_radius(ratio * parameters.alignment().radius())
+            .build()
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// current value of the preset field `name`, as written in a preset
+pub fn preset_value(parameters: &Parameters, name: &str) -> Option<String> {
+    preset_values(parameters)
+        .into_iter()
+        .find(|(field, _)| *field == name)
+        .map(|(_, value)| value)
+}
+
+/// every preset field with its current value, as written in a preset
+pub fn preset_values(parameters: &Parameters) -> Vec<(&'static str, String)> {
+    vec![
+        ("bird_size", parameters.bird_size().to_string()),
+        ("min_bird_speed", parameters.min_bird_speed().to_string()),
+        ("max_bird_speed", parameters.max_bird_speed().to_string()),
+        ("separation_radius", parameters.separation().radius().to_string()),
+        ("alignment_radius", parameters.alignment().radius().to_string()),
+        ("cohesion_radius", parameters.cohesion().radius().to_string()),
+        ("separation_dead_angle", parameters.separation().dead_angle().to_string()),
+        ("alignment_dead_angle", parameters.alignment().dead_angle().to_string()),
+        ("cohesion_dead_angle", parameters.cohesion().dead_angle().to_string()),
+        ("separation_kernel", parameters.separation().kernel().to_string()),
+        ("alignment_kernel", parameters.alignment().kernel().to_string()),
+        ("cohesion_kernel", parameters.cohesion().kernel().to_string()),
+        ("separation_factor", parameters.separation_factor().to_string()),
+        ("cohesion_factor", parameters.cohesion_factor().to_string()),
+        ("alignment_factor", parameters.alignment_factor().to_string()),
+        ("long_range_factor", parameters.long_range_factor().to_string()),
+        ("barnes_hut_theta", parameters.barnes_hut_theta().to_string()),
+        ("occlusion", parameters.occlusion().to_string()),
+        ("collisions", parameters.collisions().to_string()),
+    ]
+}
+
+pub fn set_preset_value(builder: &mut ParametersBuilder, name: &str, value: &str) -> Result<(), String> {
+    let as_f32 = || {
+        value
+            .parse::<f32>()
+            .map_err(|_| format!("invalid number '{}' for '{}'", value, name))
+    };
+    let as_bool = || {
+        value
+            .parse::<bool>()
+            .map_err(|_| format!("invalid boolean '{}' for '{}'", value, name))
+    };
+    let as_kernel = || value.parse::<Kernel>();
+
+    match name {
+        "bird_size" => builder.set_bird_size(as_f32()?),
+        "min_bird_speed" => builder.set_min_bird_speed(as_f32()?),
+        "max_bird_speed" => builder.set_max_bird_speed(as_f32()?),
+        "separation_radius" => builder.set_separation_radius(as_f32()?),
+        "alignment_radius" => builder.set_alignment_radius(as_f32()?),
+        "cohesion_radius" => builder.set_cohesion_radius(as_f32()?),
+        "separation_dead_angle" => builder.set_separation_dead_angle(as_f32()?),
+        "alignment_dead_angle" => builder.set_alignment_dead_angle(as_f32()?),
+        "cohesion_dead_angle" => builder.set_cohesion_dead_angle(as_f32()?),
+        "separation_kernel" => builder.set_separation_kernel(as_kernel()?),
+        "alignment_kernel" => builder.set_alignment_kernel(as_kernel()?),
+        "cohesion_kernel" => builder.set_cohesion_kernel(as_kernel()?),
+        // fields of the presets written before the radii and angles were set per rule,
+        // `safe_space_ratio` being converted by `parse_preset` once the whole preset is read
+        "visibility_radius" => builder.set_alignment_radius(as_f32()?).set_cohesion_radius(as_f32()?),
+        "dead_angle" => builder.set_dead_angle(as_f32()?),
+        "separation_factor" => builder.set_separation_factor(as_f32()?),
+        "cohesion_factor" => builder.set_cohesion_factor(as_f32()?),
+        "alignment_factor" => builder.set_alignment_factor(as_f32()?),
+        "long_range_factor" => builder.set_long_range_factor(as_f32()?),
+        "barnes_hut_theta" => builder.set_barnes_hut_theta(as_f32()?),
+        "occlusion" => builder.set_occlusion(as_bool()?),
+        "collisions" => builder.set_collisions(as_bool()?),
+        _ => return Err(format!("unknown parameter '{}'", name)),
+    };
+    Ok(())
+}
@@ -0,0 +1,18 @@
+use std::time::Duration;
+
+/// Time spent in the parts of the last step, only measured when profiling is enabled
+/// with [`World::set_profiling`](crate::data::world::World::set_profiling)
+#[derive(Copy, Clone, Default)]
+pub struct StepProfile {
+    /// finding the neighbours of every boid, occlusion included
+    pub neighbour_search: Duration,
+    /// the whole step, scenario excepted
+    pub total: Duration,
+}
+
+impl StepProfile {
+    /// everything but the neighbour search: steering rules, integration, collisions, predators...
+    pub fn integration(&self) -> Duration {
+        self.total.saturating_sub(self.neighbour_search)
+    }
+}
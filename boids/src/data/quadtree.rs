@@ -0,0 +1,156 @@
+use crate::data::flock::Flock;
+use crate::data::vector::Vector;
+
+/// cells are not split below this depth, the boids falling in the same cell are merged
+const MAX_DEPTH: usize = 24;
+const NO_CHILD: usize = 0;
+const NO_BOID: usize = usize::MAX;
+
+#[derive(Copy, Clone)]
+struct Node {
+    center_x: f32,
+    center_y: f32,
+    half_size: f32,
+    mass: f32,
+    sum_x: f32,
+    sum_y: f32,
+    /// index of the first of the four children, [`NO_CHILD`] for a leaf
+    first_child: usize,
+    boid: usize,
+}
+
+impl Node {
+    fn new(center_x: f32, center_y: f32, half_size: f32) -> Self {
+        Node {
+            center_x,
+            center_y,
+            half_size,
+            mass: 0.0,
+            sum_x: 0.0,
+            sum_y: 0.0,
+            first_child: NO_CHILD,
+            boid: NO_BOID,
+        }
+    }
+
+    fn quadrant(&self, x: f32, y: f32) -> usize {
+        (if x >= self.center_x { 1 } else { 0 }) + (if y >= self.center_y { 2 } else { 0 })
+    }
+}
+
+/// Barnes-Hut quadtree of the boid positions. Each cell stores the number of boids
+/// it contains and their centre of mass so that a group of distant boids can be
+/// seen as a single mass, making the long range attraction O(n log n).
+#[derive(Default)]
+pub struct QuadTree {
+    nodes: Vec<Node>,
+    stack: Vec<usize>,
+}
+
+impl QuadTree {
+    pub fn new() -> Self {
+        QuadTree::default()
+    }
+
+    /// Rebuilds the tree from the positions of the flock
+    pub fn build(&mut self, flock: &Flock) {
+        self.nodes.clear();
+        if flock.is_empty() {
+            return;
+        }
+
+        let min_x = flock.x.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max_x = flock.x.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let min_y = flock.y.iter().cloned().fold(f32::INFINITY, f32::min);
+        let max_y = flock.y.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+        let half_size = ((max_x - min_x).max(max_y - min_y) * 0.5).max(1e-3) * 1.001;
+        self.nodes.push(Node::new((min_x + max_x) * 0.5, (min_y + max_y) * 0.5, half_size));
+
+        for i in 0..flock.len() {
+            self.insert(i, flock.x[i], flock.y[i], flock);
+        }
+    }
+
+    fn insert(&mut self, boid: usize, x: f32, y: f32, flock: &Flock) {
+        let mut index = 0;
+        let mut depth = 0;
+        loop {
+            let node = &mut self.nodes[index];
+            node.mass += 1.0;
+            node.sum_x += x;
+            node.sum_y += y;
+
+            if node.first_child == NO_CHILD {
+                if node.mass == 1.0 {
+                    node.boid = boid;
+                    return;
+                }
+                if depth >= MAX_DEPTH {
+                    return;
+                }
+                let existing = node.boid;
+                node.boid = NO_BOID;
+                self.split(index);
+                let (ex, ey) = (flock.x[existing], flock.y[existing]);
+                let child = self.child_containing(index, ex, ey);
+                let child_node = &mut self.nodes[child];
+                child_node.mass = 1.0;
+                child_node.sum_x = ex;
+                child_node.sum_y = ey;
+                child_node.boid = existing;
+            }
+
+            index = self.child_containing(index, x, y);
+            depth += 1;
+        }
+    }
+
+    fn split(&mut self, index: usize) {
+        let parent = self.nodes[index];
+        let half_size = parent.half_size * 0.5;
+        self.nodes[index].first_child = self.nodes.len();
+        for quadrant in 0..4 {
+            let dx = if quadrant & 1 != 0 { half_size } else { -half_size };
+            let dy = if quadrant & 2 != 0 { half_size } else { -half_size };
+            self.nodes.push(Node::new(parent.center_x + dx, parent.center_y + dy, half_size));
+        }
+    }
+
+    fn child_containing(&self, index: usize, x: f32, y: f32) -> usize {
+        let node = &self.nodes[index];
+        node.first_child + node.quadrant(x, y)
+    }
+
+    /// Sum over every boid `j` of `(p_j - position) / (d² + softening²)^(3/2)`, `d` being the
+    /// distance between the two. A cell seen from `position` under an angle smaller than
+    /// `theta` (its size divided by the distance to its centre of mass) is used as a whole.
+    pub fn attraction(&mut self, position: &Vector, theta: f32, softening: f32) -> Vector {
+        let mut result = Vector::new();
+        if self.nodes.is_empty() {
+            return result;
+        }
+        let softening2 = softening * softening;
+
+        self.stack.clear();
+        self.stack.push(0);
+        while let Some(index) = self.stack.pop() {
+            let node = &self.nodes[index];
+            if node.mass == 0.0 {
+                continue;
+            }
+            let dx = node.sum_x / node.mass - position.x;
+            let dy = node.sum_y / node.mass - position.y;
+            let distance2 = dx * dx + dy * dy;
+            let size = node.half_size * 2.0;
+
+            if node.first_child == NO_CHILD || size * size < theta * theta * distance2 {
+                let denominator = (distance2 + softening2).powf(1.5);
+                result.x += node.mass * dx / denominator;
+                result.y += node.mass * dy / denominator;
+            } else {
+                self.stack.extend(node.first_child..node.first_child + 4);
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,253 @@
+use std::fs;
+
+use crate::data::obstacle::Obstacle;
+use crate::data::parameters::ParametersBuilder;
+use crate::data::preset::{preset_value, set_preset_value};
+use crate::data::vector::Vector;
+use crate::data::world::{World, MAX_SPAWN_COUNT};
+
+/// What happens at a given time of a scenario
+#[derive(Clone)]
+pub enum ScenarioAction {
+    /// sets the preset field `name` to `value`
+    Set { name: String, value: String },
+    /// linearly changes the preset field `name` from its current value to `target` in `duration` seconds
+    Ramp { name: String, target: f32, duration: f32 },
+    Spawn(usize),
+    Despawn(usize),
+    AddObstacle(Obstacle),
+    ReleasePredator(Vector),
+}
+
+#[derive(Clone)]
+pub struct ScenarioEntry {
+    /// simulation time in seconds
+    pub time: f32,
+    pub action: ScenarioAction,
+}
+
+#[derive(Clone)]
+struct ActiveRamp {
+    name: String,
+    start_time: f32,
+    start_value: f32,
+    target: f32,
+    duration: f32,
+}
+
+/// A timeline of parameter changes and world events executed by the
+/// [`World`] as its simulation time advances.
+///
+/// A scenario file has one entry per line, `time action arguments...`,
+/// `#` starting a comment line:
+///
+/// ```text
+/// 10  set       collisions true
+/// 30  ramp      separation_factor 0.2 5
+/// 45  spawn     200
+/// 50  despawn   100
+/// 60  obstacle  2.0 1.0 0.5
+/// 60  predator  -8.0 0.0
+/// ```
+///
+/// `set` and `ramp` take the name of a preset field, `ramp` its target value then the
+/// duration of the ramp in seconds. `spawn` grows the flock up to [`MAX_SPAWN_COUNT`] boids.
+/// `obstacle` takes the center and the radius of the obstacle, `predator` the position
+/// where the predator is released.
+#[derive(Clone)]
+pub struct Scenario {
+    entries: Vec<ScenarioEntry>,
+    next_entry: usize,
+    ramps: Vec<ActiveRamp>,
+}
+
+impl Scenario {
+    /// `entries` are sorted by time, entries with the same time keep their order
+    pub fn new(mut entries: Vec<ScenarioEntry>) -> Self {
+        entries.sort_by(|e1, e2| e1.time.total_cmp(&e2.time));
+        Scenario {
+            entries,
+            next_entry: 0,
+            ramps: Vec::new(),
+        }
+    }
+
+    pub fn entries(&self) -> &[ScenarioEntry] {
+        &self.entries
+    }
+
+    /// true when every entry was executed and every ramp is over
+    pub fn is_finished(&self) -> bool {
+        self.next_entry >= self.entries.len() && self.ramps.is_empty()
+    }
+
+    /// Rewinds the scenario so that it is executed again from time 0
+    pub fn restart(&mut self) {
+        self.next_entry = 0;
+        self.ramps.clear();
+    }
+
+    /// Executes the entries due at `world.time` and advances the running ramps.
+    /// Returns the errors of the parameter changes that were rejected.
+    pub fn update(&mut self, world: &mut World) -> Vec<String> {
+        let time = world.time;
+        let mut errors = Vec::new();
+
+        while self.next_entry < self.entries.len() && self.entries[self.next_entry].time <= time {
+            let entry = self.entries[self.next_entry].clone();
+            self.next_entry += 1;
+            if let Err(error) = self.execute(&entry, world) {
+                errors.push(format!("t={}: {}", entry.time, error));
+            }
+        }
+
+        for ramp in self.ramps.iter() {
+            let progress = ((time - ramp.start_time) / ramp.duration).clamp(0.0, 1.0);
+            let value = ramp.start_value + (ramp.target - ramp.start_value) * progress;
+            if let Err(error) = set_parameter(world, &ramp.name, &value.to_string()) {
+                errors.push(format!("t={}: {}", time, error));
+            }
+        }
+        self.ramps.retain(|ramp| time < ramp.start_time + ramp.duration);
+
+        errors
+    }
+
+    fn execute(&mut self, entry: &ScenarioEntry, world: &mut World) -> Result<(), String> {
+        match &entry.action {
+            ScenarioAction::Set { name, value } => set_parameter(world, name, value)?,
+            ScenarioAction::Ramp { name, target, duration } => {
+                self.ramps.retain(|ramp| ramp.name != *name);
+                if *duration <= 0.0 {
+                    set_parameter(world, name, &target.to_string())?;
+                } else {
+                    let start_value = preset_value(&world.parameters, name)
+                        .and_then(|value| value.parse::<f32>().ok())
+                        .ok_or_else(|| format!("'{}' cannot be ramped", name))?;
+                    self.ramps.push(ActiveRamp {
+                        name: name.clone(),
+                        start_time: entry.time,
+                        start_value,
+                        target: *target,
+                        duration: *duration,
+                    });
+                }
+            }
+            ScenarioAction::Spawn(nb_birds) => world.spawn_boids((*nb_birds).min(world.spawn_capacity())),
+            ScenarioAction::Despawn(nb_birds) => world.despawn_boids(*nb_birds),
+            ScenarioAction::AddObstacle(obstacle) => world.obstacles.push(*obstacle),
+            ScenarioAction::ReleasePredator(position) => world.release_predator(position.x, position.y),
+        }
+        Ok(())
+    }
+}
+
+fn set_parameter(world: &mut World, name: &str, value: &str) -> Result<(), String> {
+    let mut result = Ok(());
+    world
+        .update_parameters(|builder| result = set_preset_value(builder, name, value))
+        .map_err(|e| e.to_string())?;
+    result
+}
+
+pub fn load_scenario(path: &str) -> Result<Scenario, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Cannot read scenario '{}': {}", path, e))?;
+    parse_scenario(&content).map_err(|e| format!("Invalid scenario '{}': {}", path, e))
+}
+
+pub fn parse_scenario(content: &str) -> Result<Scenario, String> {
+    let mut entries = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let entry = parse_entry(line).map_err(|e| format!("line {}: {}", line_number + 1, e))?;
+        entries.push(entry);
+    }
+    Ok(Scenario::new(entries))
+}
+
+fn parse_entry(line: &str) -> Result<ScenarioEntry, String> {
+    let tokens: Vec<&str> = line.split_whitespace().collect();
+    if tokens.len() < 2 {
+        return Err("expected 'time action arguments...'".to_string());
+    }
+    let time = parse_number::<f32>(tokens[0], "time")?;
+    if !time.is_finite() {
+        return Err(format!("invalid time '{}'", tokens[0]));
+    }
+    let arguments = &tokens[2..];
+    let expect_arguments = |count: usize| {
+        if arguments.len() == count {
+            Ok(())
+        } else {
+            Err(format!("'{}' expects {} arguments but got {}", tokens[1], count, arguments.len()))
+        }
+    };
+
+    let action = match tokens[1] {
+        "set" => {
+            expect_arguments(2)?;
+            set_preset_value(&mut ParametersBuilder::new(), arguments[0], arguments[1])?;
+            ScenarioAction::Set {
+                name: arguments[0].to_string(),
+                value: arguments[1].to_string(),
+            }
+        }
+        "ramp" => {
+            expect_arguments(3)?;
+            let target = parse_number::<f32>(arguments[1], "ramp target")?;
+            set_preset_value(&mut ParametersBuilder::new(), arguments[0], arguments[1])?;
+            let duration = parse_number::<f32>(arguments[2], "ramp duration")?;
+            if !(duration.is_finite() && duration >= 0.0) {
+                return Err(format!("invalid ramp duration '{}', it must be finite and not negative", arguments[2]));
+            }
+            ScenarioAction::Ramp {
+                name: arguments[0].to_string(),
+                target,
+                duration,
+            }
+        }
+        "spawn" => {
+            expect_arguments(1)?;
+            let nb_birds = parse_number::<usize>(arguments[0], "number of boids")?;
+            if nb_birds > MAX_SPAWN_COUNT {
+                return Err(format!("cannot spawn {} boids, at most {} can be spawned", nb_birds, MAX_SPAWN_COUNT));
+            }
+            ScenarioAction::Spawn(nb_birds)
+        }
+        "despawn" => {
+            expect_arguments(1)?;
+            ScenarioAction::Despawn(parse_number::<usize>(arguments[0], "number of boids")?)
+        }
+        "obstacle" => {
+            expect_arguments(3)?;
+            let radius = parse_number::<f32>(arguments[2], "obstacle radius")?;
+            if !(radius.is_finite() && radius > 0.0) {
+                return Err(format!("invalid obstacle radius '{}', it must be finite and positive", arguments[2]));
+            }
+            ScenarioAction::AddObstacle(Obstacle::new(
+                parse_number::<f32>(arguments[0], "obstacle x")?,
+                parse_number::<f32>(arguments[1], "obstacle y")?,
+                radius,
+            ))
+        }
+        "predator" => {
+            expect_arguments(2)?;
+            ScenarioAction::ReleasePredator(Vector {
+                x: parse_number::<f32>(arguments[0], "predator x")?,
+                y: parse_number::<f32>(arguments[1], "predator y")?,
+            })
+        }
+        action => return Err(format!("unknown action '{}'", action)),
+    };
+    Ok(ScenarioEntry { time, action })
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, what: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("invalid {} '{}'", what, value))
+}
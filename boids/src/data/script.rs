@@ -0,0 +1,132 @@
+use std::fs;
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+
+use crate::data::boid::Boid;
+use crate::data::flock::Flock;
+use crate::data::neighbour::{Neighbour, IN_ALIGNMENT_RANGE, IN_COHESION_RANGE, IN_SEPARATION_RANGE};
+use crate::data::vector::Vector;
+
+const STEER_FUNCTION: &str = "steer";
+/// most operations of one call of the steering function, stopping the scripts looping forever
+const MAX_OPERATIONS: u64 = 1_000_000;
+/// most nested function calls, stopping the scripts recursing forever
+const MAX_CALL_LEVELS: usize = 32;
+
+/// A custom steering rule written in [Rhai](https://rhai.rs).
+///
+/// The script must define a `steer(boid, neighbours)` function returning the
+/// velocity change of the boid as an array `[dx, dy]`. It is added to the
+/// velocity after the separation, alignment and cohesion rules.
+///
+/// `boid` is a map with the fields `index`, `x`, `y`, `vx`, `vy` and `speed`.
+/// `neighbours` is an array of maps with the same fields plus `distance`, the
+/// offset `dx`, `dy` from the neighbour to the boid and the booleans `separation`,
+/// `alignment` and `cohesion` telling which rules perceive it. The boid itself
+/// is not part of its neighbours.
+///
+/// ```text
+/// // steers towards the center of the playfield
+/// fn steer(boid, neighbours) {
+///     [-boid.x * 0.01, -boid.y * 0.01]
+/// }
+/// ```
+///
+/// A call running more than [`MAX_OPERATIONS`] operations or nesting more than
+/// [`MAX_CALL_LEVELS`] function calls fails.
+pub struct SteeringScript {
+    engine: Engine,
+    ast: AST,
+}
+
+impl SteeringScript {
+    pub fn compile(source: &str) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(MAX_CALL_LEVELS);
+        let ast = engine.compile(source).map_err(|e| e.to_string())?;
+        let has_steer_function = ast
+            .iter_functions()
+            .any(|function| function.name == STEER_FUNCTION && function.params.len() == 2);
+        if !has_steer_function {
+            return Err(format!("the script does not define '{}(boid, neighbours)'", STEER_FUNCTION));
+        }
+        Ok(SteeringScript { engine, ast })
+    }
+
+    /// Computes the velocity change of the boid at `index`
+    pub fn steer(&self, index: usize, boid: &Boid, neighbours: &[Neighbour], flock: &Flock) -> Result<Vector, String> {
+        let boid_map = boid_to_map(index, &boid.position, &boid.velocity, boid.speed());
+        let neighbours: Array = neighbours
+            .iter()
+            .filter(|neighbour| neighbour.index != index)
+            .map(|neighbour| {
+                let mut map = boid_to_map(
+                    neighbour.index,
+                    &flock.position(neighbour.index),
+                    &flock.velocity(neighbour.index),
+                    flock.speed[neighbour.index],
+                );
+                map.insert("distance".into(), Dynamic::from_float(neighbour.distance));
+                map.insert("dx".into(), Dynamic::from_float(neighbour.offset.x));
+                map.insert("dy".into(), Dynamic::from_float(neighbour.offset.y));
+                let visibility = neighbour.visibility;
+                map.insert("separation".into(), Dynamic::from_bool(visibility & IN_SEPARATION_RANGE != 0));
+                map.insert("alignment".into(), Dynamic::from_bool(visibility & IN_ALIGNMENT_RANGE != 0));
+                map.insert("cohesion".into(), Dynamic::from_bool(visibility & IN_COHESION_RANGE != 0));
+                Dynamic::from_map(map)
+            })
+            .collect();
+
+        let result: Array = self
+            .engine
+            .call_fn(&mut Scope::new(), &self.ast, STEER_FUNCTION, (boid_map, neighbours))
+            .map_err(|e| match *e {
+                EvalAltResult::ErrorTooManyOperations(_) => {
+                    format!("'{}' ran more than {} operations", STEER_FUNCTION, MAX_OPERATIONS)
+                }
+                EvalAltResult::ErrorStackOverflow(_) => {
+                    format!("'{}' nested more than {} function calls", STEER_FUNCTION, MAX_CALL_LEVELS)
+                }
+                _ => e.to_string(),
+            })?;
+
+        match result.as_slice() {
+            [x, y] => Ok(Vector {
+                x: to_f32(x)?,
+                y: to_f32(y)?,
+            }),
+            _ => Err(format!("'{}' must return [dx, dy] but returned {} values", STEER_FUNCTION, result.len())),
+        }
+    }
+}
+
+fn boid_to_map(index: usize, position: &Vector, velocity: &Vector, speed: f32) -> Map {
+    let mut map = Map::new();
+    map.insert("index".into(), Dynamic::from_int(index as rhai::INT));
+    map.insert("x".into(), Dynamic::from_float(position.x));
+    map.insert("y".into(), Dynamic::from_float(position.y));
+    map.insert("vx".into(), Dynamic::from_float(velocity.x));
+    map.insert("vy".into(), Dynamic::from_float(velocity.y));
+    map.insert("speed".into(), Dynamic::from_float(speed));
+    map
+}
+
+/// the number returned by the script, which must be finite not to spoil the velocity for good
+fn to_f32(value: &Dynamic) -> Result<f32, String> {
+    let number = value
+        .as_float()
+        .or_else(|_| value.as_int().map(|i| i as f32))
+        .map_err(|type_name| format!("'{}' must return numbers but returned a {}", STEER_FUNCTION, type_name))?;
+    if number.is_finite() {
+        Ok(number)
+    } else {
+        Err(format!("'{}' must return finite numbers but returned {}", STEER_FUNCTION, number))
+    }
+}
+
+pub fn load_script(path: &str) -> Result<SteeringScript, String> {
+    let source =
+        fs::read_to_string(path).map_err(|e| format!("Cannot read script '{}': {}", path, e))?;
+    SteeringScript::compile(&source).map_err(|e| format!("Invalid script '{}': {}", path, e))
+}
@@ -0,0 +1,74 @@
+use crate::data::vector::Vector;
+use crate::data::world::World;
+
+/// Recent positions of every boid, one ring buffer of `length` positions per boid,
+/// a position being recorded every `decimation` steps
+pub struct Trails {
+    length: usize,
+    decimation: usize,
+    nb_steps: usize,
+    /// the ring buffer of boid `i` is `positions[i * length..(i + 1) * length]`
+    positions: Vec<Vector>,
+    /// slot of the ring buffers written by the next record
+    next_slot: usize,
+    nb_recorded: usize,
+    last_time: f32,
+}
+
+impl Trails {
+    pub fn new(length: usize, decimation: usize) -> Self {
+        Trails {
+            length: length.max(1),
+            decimation: decimation.max(1),
+            nb_steps: 0,
+            positions: Vec::new(),
+            next_slot: 0,
+            nb_recorded: 0,
+            last_time: 0.0,
+        }
+    }
+
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn nb_birds(&self) -> usize {
+        self.positions.len() / self.length
+    }
+
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.next_slot = 0;
+        self.nb_recorded = 0;
+        self.nb_steps = 0;
+    }
+
+    /// To be called after each step. The trails are restarted when the number of
+    /// boids changes or when the simulation time goes back, after a reset.
+    pub fn record(&mut self, world: &World) {
+        let flock = &world.current;
+        if flock.len() != self.nb_birds() || world.time < self.last_time {
+            self.clear();
+            self.positions.resize(flock.len() * self.length, Vector::new());
+        }
+        self.last_time = world.time;
+
+        let step = self.nb_steps;
+        self.nb_steps += 1;
+        if !step.is_multiple_of(self.decimation) {
+            return;
+        }
+        for i in 0..flock.len() {
+            self.positions[i * self.length + self.next_slot] = flock.position(i);
+        }
+        self.next_slot = (self.next_slot + 1) % self.length;
+        self.nb_recorded = (self.nb_recorded + 1).min(self.length);
+    }
+
+    /// recorded positions of the boid at `index`, from the oldest to the newest
+    pub fn trail(&self, index: usize) -> impl Iterator<Item = &Vector> + '_ {
+        let buffer = &self.positions[index * self.length..(index + 1) * self.length];
+        let first_slot = (self.next_slot + self.length - self.nb_recorded) % self.length;
+        (0..self.nb_recorded).map(move |k| &buffer[(first_slot + k) % self.length])
+    }
+}
@@ -1,5 +1,7 @@
 use std::f32::consts::PI;
 
+use rand::Rng;
+
 #[derive(Copy, Clone)]
 pub struct Vector {
     pub x: f32,
@@ -44,8 +46,8 @@ impl Vector {
         self.x.hypot(self.y)
     }
 
-    pub fn set_random(&mut self, norm:f32) {
-        let angle: f32 = rand::random::<f32>() * PI * 2.0;
+    pub fn set_random(&mut self, norm:f32, rng: &mut impl Rng) {
+        let angle: f32 = rng.gen::<f32>() * PI * 2.0;
         self.x = norm * angle.cos();
         self.y = norm * angle.sin();
     }
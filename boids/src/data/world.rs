@@ -1,82 +1,74 @@
-use rand::Rng;
+use std::sync::Arc;
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 use crate::data::boid::Boid;
+use crate::data::collision::resolve_collisions;
+use crate::data::event::{Side, SimulationEvent, SimulationObserver};
+use crate::data::flock::Flock;
+use crate::data::grid::CellGrid;
+use crate::data::neighbour::{Neighbour, IN_ALIGNMENT_RANGE, IN_COHESION_RANGE, IN_SEPARATION_RANGE};
+use crate::data::obstacle::Obstacle;
+use crate::data::occlusion::{remove_occluded, PerceptionStats};
+use crate::data::parameters::{
+    Parameters, ParametersBuilder, ParametersError, DEFAULT_BIRD_MIN_SPEED, DEFAULT_BIRD_SIZE,
+};
+use crate::data::predator::{chase, create_predator, flee, PREDATOR_SPEED};
+use crate::data::profile::StepProfile;
+use crate::data::quadtree::QuadTree;
+use crate::data::scenario::Scenario;
+use crate::data::script::SteeringScript;
 use crate::data::steering::Steering;
 use crate::data::vector::Vector;
 
 const CONSTRAINT_STRENGTH: f32 = 0.1;
-
-const SAFE_SPACE_RATIO: f32 = 0.8;
-const DEFAULT_VISIBILITY_FACTOR: f32 = 3.0;
-
-// in degree
-const DEAD_ANGLE: f32 = 20.0;
-const DEFAULT_SEPARATION_FACTOR: f32 = 6.0;
-const DEFAULT_COHESION_FACTOR: f32 = 4.0;
-const DEFAULT_ALIGNMENT_FACTOR: f32 = 10.;
+const CONSTRAINT_LIMIT_X: f32 = 0.9;
+const CONSTRAINT_LIMIT_Y: f32 = 0.8;
 
 const RANDOM_FACTOR: f32 = 0.0;
-const DEFAULT_BIRD_SIZE: f32 = 0.2;
-const DEFAULT_BIRD_MIN_SPEED: f32 = 5.0;
-const DEFAULT_BIRD_MAX_SPEED: f32 = 16.0;
-
-const NOT_VISIBLE: u8 = 0;
-const VISIBLE: u8 = 1;
-const IN_SAFE_SPACE: u8 = 2;
-
-pub struct Parameters {
-    pub bird_size: f32,
-    pub min_bird_speed: f32,
-    pub max_bird_speed: f32,
-    pub visibility_radius: f32,
-    pub safe_space_ratio: f32,
-    dead_angle: f32,
-    cos_max_angle: f32,
-    pub separation_factor: f32,
-    pub cohesion_factor: f32,
-    pub alignment_factor: f32,
-}
-
-
-pub fn compute_cos_max_angle(dead_angle: f32) -> f32 {
-    (std::f32::consts::PI * (1. - dead_angle / 180.)).cos()
-}
 
-impl Parameters {
-    pub fn new() -> Self {
-        Parameters {
-            bird_size: DEFAULT_BIRD_SIZE,
-            visibility_radius: DEFAULT_BIRD_SIZE * DEFAULT_VISIBILITY_FACTOR,
-            safe_space_ratio: SAFE_SPACE_RATIO,
-            dead_angle: DEAD_ANGLE,
-            cos_max_angle: compute_cos_max_angle(DEAD_ANGLE),
-            cohesion_factor: DEFAULT_COHESION_FACTOR * 0.01,
-            separation_factor: DEFAULT_SEPARATION_FACTOR * 0.01,
-            alignment_factor: DEFAULT_ALIGNMENT_FACTOR * 0.01,
-            min_bird_speed: DEFAULT_BIRD_MIN_SPEED,
-            max_bird_speed: DEFAULT_BIRD_MAX_SPEED,
-        }
-    }
-
-    pub fn dead_angle(&self) -> f32 {
-        self.dead_angle
-    }
+/// most boids of a flock grown by the spawn requests of the remote controls
+pub const MAX_SPAWN_COUNT: usize = 100_000;
 
-    pub fn cos_max_angle(&self) -> f32 {
-        self.cos_max_angle
-    }
-
-    pub fn set_dead_angle(&mut self, dead_angle: f32) {
-        self.dead_angle = dead_angle;
-        self.cos_max_angle = compute_cos_max_angle(dead_angle);
-    }
+/// What a boid perceives in the current state of the world, as used by the next step
+pub struct BoidPerception {
+    /// the boids perceived by at least one rule, the occluded ones removed and the boid itself excluded
+    pub neighbours: Vec<Neighbour>,
+    /// the steering of each rule, before being scaled by the factor of the rule
+    pub steering: Steering,
 }
 
 pub struct World {
     pub playfield_size: f32,
     pub parameters: Parameters,
-    pub current: Vec<Boid>,
-    pub next: Vec<Boid>,
+    pub current: Flock,
+    pub next: Flock,
+    pub perception_stats: PerceptionStats,
+    /// number of boid-boid collisions resolved during the last step
+    pub nb_collisions: usize,
+    /// number of boids caught by a predator during the last step
+    pub nb_catches: usize,
+    pub obstacles: Vec<Obstacle>,
+    pub predators: Vec<Boid>,
+    /// simulation time in seconds, the sum of the durations of the computed steps
+    pub time: f32,
+    pub profile: StepProfile,
+    profiling: bool,
+    scenario: Option<Scenario>,
+    script: Option<Arc<SteeringScript>>,
+    quadtree: QuadTree,
+    grid: CellGrid,
+    /// the neighbours perceived by every boid during the step, those of the boid at `i`
+    /// being `perceived[perceived_start[i]..perceived_start[i + 1]]`
+    perceived: Vec<Neighbour>,
+    perceived_start: Vec<usize>,
+    collision_grid: CellGrid,
+    collision_buffer: Vec<(usize, usize)>,
+    observers: Vec<Box<dyn SimulationObserver>>,
+    events: Vec<SimulationEvent>,
+    rng: StdRng,
 }
 
 impl World {
@@ -84,61 +76,245 @@ impl World {
         World {
             playfield_size: playfield,
             parameters: Parameters::new(),
-            current: vec![Boid::new(); nb_birds],
-            next: vec![Boid::new(); nb_birds],
+            current: Flock::new(nb_birds),
+            next: Flock::new(nb_birds),
+            perception_stats: PerceptionStats::default(),
+            nb_collisions: 0,
+            nb_catches: 0,
+            obstacles: Vec::new(),
+            predators: Vec::new(),
+            time: 0.0,
+            profile: StepProfile::default(),
+            profiling: false,
+            scenario: None,
+            script: None,
+            quadtree: QuadTree::new(),
+            grid: CellGrid::new(),
+            perceived: Vec::new(),
+            perceived_start: Vec::new(),
+            collision_grid: CellGrid::new(),
+            collision_buffer: Vec::new(),
+            observers: Vec::new(),
+            events: Vec::new(),
+            rng: StdRng::from_entropy(),
         }
     }
 
+    /// Reseeds the random generator used for initialisation and steering,
+    /// making the following steps reproducible
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
     pub fn initialize(&mut self) {
-        let mut rng = rand::thread_rng();
-        for boid in self.current.iter_mut() {
-            // let t: f32 = rng.gen::<f32>() * 2.0 * PI;
-            // let u = rng.gen::<f32>() + rng.gen::<f32>();
-            // let r: f32 = if u > 1.0 { 2.0 - u } else { u };
-
-            // boid.position.x = r * t.cos() * self.playfield_size;
-            // boid.position.y = r * t.sin() * self.playfield_size;
-            boid.position.x = (rng.gen::<f32>() - 0.5) * self.playfield_size;
-            boid.position.y = (rng.gen::<f32>() - 0.5) * self.playfield_size;
-            boid.velocity.x = (rng.gen::<f32>() - 0.5) * self.playfield_size * 0.1;
-            boid.velocity.y = (rng.gen::<f32>() - 0.5) * self.playfield_size * 0.1;
-            boid.update_speed();
-
-            boid.clamp_speed(
-                self.parameters.min_bird_speed,
-                self.parameters.max_bird_speed,
-            );
+        for i in 0..self.current.len() {
+            let boid = self.random_boid();
+            self.current.set_boid(i, &boid);
+        }
+    }
+
+    /// half width and half height of the rectangle the boids are steered back into
+    pub fn boundary(&self) -> (f32, f32) {
+        (self.playfield_size * CONSTRAINT_LIMIT_X, self.playfield_size * CONSTRAINT_LIMIT_Y)
+    }
+
+    /// Restarts the simulation: the boids are placed randomly again, the obstacles and
+    /// predators are removed and the scenario, if any, is rewound to time 0
+    pub fn reset(&mut self) {
+        self.initialize();
+        self.obstacles.clear();
+        self.predators.clear();
+        self.nb_collisions = 0;
+        self.nb_catches = 0;
+        self.time = 0.0;
+        if let Some(scenario) = self.scenario.as_mut() {
+            scenario.restart();
+        }
+    }
+
+    fn random_boid(&mut self) -> Boid {
+        let rng = &mut self.rng;
+        let mut boid = Boid::new();
+        // let t: f32 = rng.gen::<f32>() * 2.0 * PI;
+        // let u = rng.gen::<f32>() + rng.gen::<f32>();
+        // let r: f32 = if u > 1.0 { 2.0 - u } else { u };
+
+        // boid.position.x = r * t.cos() * self.playfield_size;
+        // boid.position.y = r * t.sin() * self.playfield_size;
+        boid.position.x = (rng.gen::<f32>() - 0.5) * self.playfield_size;
+        boid.position.y = (rng.gen::<f32>() - 0.5) * self.playfield_size;
+        boid.velocity.x = (rng.gen::<f32>() - 0.5) * self.playfield_size * 0.1;
+        boid.velocity.y = (rng.gen::<f32>() - 0.5) * self.playfield_size * 0.1;
+        boid.update_speed();
+
+        boid.clamp_speed(
+            self.parameters.min_bird_speed(),
+            self.parameters.max_bird_speed(),
+            rng,
+        );
+        boid
+    }
+
+    /// Applies `update` to a builder initialised with the current parameters and
+    /// replaces them if the result is valid. The parameters are left untouched otherwise.
+    pub fn update_parameters<F>(&mut self, update: F) -> Result<(), ParametersError>
+    where
+        F: FnOnce(&mut ParametersBuilder),
+    {
+        let mut builder = self.parameters.to_builder();
+        update(&mut builder);
+        self.parameters = builder.build()?;
+        Ok(())
+    }
+
+    /// The scenario is executed from the current simulation time on
+    pub fn set_scenario(&mut self, scenario: Scenario) {
+        self.scenario = Some(scenario);
+    }
+
+    pub fn scenario(&self) -> Option<&Scenario> {
+        self.scenario.as_ref()
+    }
+
+    /// The script steering is added to the built-in rules of every boid. It is
+    /// removed after its first error, reported as a [`SimulationEvent::ScriptError`].
+    pub fn set_script(&mut self, script: Option<Arc<SteeringScript>>) {
+        self.script = script;
+    }
+
+    /// When enabled, [`World::profile`] is filled at each step
+    pub fn set_profiling(&mut self, profiling: bool) {
+        self.profiling = profiling;
+        self.profile = StepProfile::default();
+    }
+
+    pub fn release_predator(&mut self, x: f32, y: f32) {
+        self.predators.push(create_predator(x, y));
+        if self.is_observed() {
+            self.events.push(SimulationEvent::PredatorReleased { predator: self.predators.len() - 1 });
+        }
+        self.dispatch_events();
+    }
+
+    pub fn add_observer(&mut self, observer: Box<dyn SimulationObserver>) {
+        self.observers.push(observer);
+    }
+
+    /// Adds `nb_birds` randomly placed boids at the end of the flock
+    pub fn spawn_boids(&mut self, nb_birds: usize) {
+        for _ in 0..nb_birds {
+            let boid = self.random_boid();
+            self.current.push(&boid);
+            self.next.push(&boid);
+            if self.is_observed() {
+                self.events.push(SimulationEvent::Spawned { boid: self.current.len() - 1 });
+            }
+        }
+        self.dispatch_events();
+    }
+
+    /// number of boids the remote controls may still spawn, the flock growing up to [`MAX_SPAWN_COUNT`] boids
+    pub fn spawn_capacity(&self) -> usize {
+        MAX_SPAWN_COUNT.saturating_sub(self.current.len())
+    }
+
+    /// Removes the last `nb_birds` boids of the flock
+    pub fn despawn_boids(&mut self, nb_birds: usize) {
+        for _ in 0..nb_birds.min(self.current.len()) {
+            self.current.pop();
+            self.next.pop();
+            if self.is_observed() {
+                self.events.push(SimulationEvent::Despawned { boid: self.current.len() });
+            }
+        }
+        self.dispatch_events();
+    }
+
+    /// the events are only collected when someone observes them
+    fn is_observed(&self) -> bool {
+        !self.observers.is_empty()
+    }
+
+    fn dispatch_events(&mut self) {
+        for event in self.events.drain(..) {
+            for observer in self.observers.iter_mut() {
+                observer.on_event(&event);
+            }
         }
     }
 
     pub fn compute(&mut self, dt: f32) {
+        let observed = self.is_observed();
+        if let Some(mut scenario) = self.scenario.take() {
+            for message in scenario.update(self) {
+                if observed {
+                    self.events.push(SimulationEvent::ScenarioError { message });
+                }
+            }
+            self.scenario = Some(scenario);
+        }
+
+        let step_start = self.profiling.then(Instant::now);
+        let mut profile = StepProfile::default();
         let mut steering = Steering::new();
-        let mut rng = rand::thread_rng();
+        let mut neighbours: Vec<Neighbour> = Vec::new();
+        let mut perception_stats = PerceptionStats::default();
+        let mut rng = self.rng.clone();
+        let mut script = self.script.clone();
 
         let nb_birds = self.current.len();
+        // every boid perceives the current state before any of them steers, so that the
+        // neighbour search is timed once per step
+        let search_start = self.profiling.then(Instant::now);
+        self.grid.build(&self.current, self.parameters.max_radius());
+        let mut perceived = std::mem::take(&mut self.perceived);
+        let mut perceived_start = std::mem::take(&mut self.perceived_start);
+        perceived.clear();
+        perceived_start.clear();
+        perceived_start.push(0);
+        for i in 0..nb_birds {
+            self.perceive(&self.grid, i, &mut neighbours, &mut perception_stats);
+            perceived.extend_from_slice(&neighbours);
+            perceived_start.push(perceived.len());
+        }
+        if let Some(start) = search_start {
+            profile.neighbour_search = start.elapsed();
+        }
+        let long_range_factor = self.parameters.long_range_factor();
+        if long_range_factor > 0.0 {
+            self.quadtree.build(&self.current);
+        }
+        // the attraction is averaged over the flock and is of order 1 for boids at the playfield scale
+        let long_range_scale =
+            long_range_factor * self.playfield_size * self.playfield_size / (nb_birds.max(1) as f32);
         for i in 0..nb_birds {
-            let boid = &self.current[i];
-            let has_neighbours = self.compute_steering(*boid, &mut steering);
-            let mut target: &mut Boid = &mut self.next[i];
-            target.position = boid.position;
-            target.velocity = boid.velocity;
+            let boid = self.current.boid(i);
+            let neighbours = &perceived[perceived_start[i]..perceived_start[i + 1]];
+            let has_neighbours = self.compute_steering(i, &mut steering, neighbours, &mut rng);
+            if observed {
+                for neighbour in neighbours.iter() {
+                    if neighbour.index != i && neighbour.distance < self.parameters.bird_size() {
+                        self.events.push(SimulationEvent::NearCollision {
+                            first: i,
+                            second: neighbour.index,
+                            distance: neighbour.distance,
+                        });
+                    }
+                }
+            }
+            let mut target = boid;
             target.update_position(dt);
 
             if has_neighbours {
-                let current = target.velocity;
-
                 target
                     .velocity
-                    .add_scaled(&steering.separation, self.parameters.separation_factor);
+                    .add_scaled(&steering.separation, self.parameters.separation_factor());
                 target
                     .velocity
-                    .add_scaled(&steering.alignment, self.parameters.alignment_factor);
+                    .add_scaled(&steering.alignment, self.parameters.alignment_factor());
                 target
                     .velocity
-                    .add_scaled(&steering.cohesion, self.parameters.cohesion_factor);
-                target
-                    .velocity
-                    .add_scaled(&current, -self.parameters.alignment_factor);
+                    .add_scaled(&steering.cohesion, self.parameters.cohesion_factor());
 
                 target.velocity.x +=
                     target.velocity.x * (2.0 * rng.gen::<f32>() - 1.0) * RANDOM_FACTOR;
@@ -146,90 +322,259 @@ impl World {
                     target.velocity.y * (2.0 * rng.gen::<f32>() - 1.0) * RANDOM_FACTOR;
 
             }
+            if long_range_factor > 0.0 {
+                let attraction = self.quadtree.attraction(
+                    &boid.position,
+                    self.parameters.barnes_hut_theta(),
+                    self.parameters.cohesion().radius(),
+                );
+                target.velocity.add_scaled(&attraction, long_range_scale);
+            }
+            if let Some(steering_script) = script.as_ref() {
+                match steering_script.steer(i, &boid, neighbours, &self.current) {
+                    Ok(change) => target.velocity.add(&change),
+                    Err(message) => {
+                        if observed {
+                            self.events.push(SimulationEvent::ScriptError { message });
+                        }
+                        script = None;
+                        self.script = None;
+                    }
+                }
+            }
+            for obstacle in self.obstacles.iter() {
+                obstacle.avoid(&mut target, self.parameters.bird_size());
+            }
+            for predator in self.predators.iter() {
+                flee(&mut target, predator);
+            }
             target.update_speed();
             target.clamp_speed(
-                self.parameters.min_bird_speed,
-                self.parameters.max_bird_speed,
+                self.parameters.min_bird_speed(),
+                self.parameters.max_bird_speed(),
+                &mut rng,
             );
             constraint_boid_rect(&mut target, self.playfield_size);
+            self.next.set_boid(i, &target);
+
+            if observed {
+                for side in Side::ALL.iter() {
+                    if is_beyond(&target.position, *side, self.playfield_size)
+                        && !is_beyond(&boid.position, *side, self.playfield_size)
+                    {
+                        self.events.push(SimulationEvent::BoundaryContact { boid: i, side: *side });
+                    }
+                }
+            }
         }
 
-        self.current.swap_with_slice(&mut self.next);
+        self.collision_buffer.clear();
+        if self.parameters.collisions() {
+            resolve_collisions(
+                &mut self.next,
+                self.parameters.bird_size(),
+                &mut self.collision_grid,
+                &mut self.collision_buffer,
+            );
+            if observed {
+                for (first, second) in self.collision_buffer.iter() {
+                    self.events.push(SimulationEvent::Collision { first: *first, second: *second });
+                }
+            }
+        }
+        self.nb_collisions = self.collision_buffer.len();
+
+        self.nb_catches = 0;
+        for (index, predator) in self.predators.iter_mut().enumerate() {
+            if let Some((boid, distance)) = chase(predator, &self.current) {
+                if distance < self.parameters.bird_size() {
+                    self.nb_catches += 1;
+                    if observed {
+                        self.events.push(SimulationEvent::PredatorCatch { predator: index, boid });
+                    }
+                }
+            }
+            predator.clamp_speed(PREDATOR_SPEED, PREDATOR_SPEED, &mut rng);
+            predator.update_position(dt);
+            for obstacle in self.obstacles.iter() {
+                obstacle.avoid(predator, self.parameters.bird_size());
+            }
+            constraint_boid_rect(predator, self.playfield_size);
+            predator.update_speed();
+        }
+
+        std::mem::swap(&mut self.current, &mut self.next);
+        self.perceived = perceived;
+        self.perceived_start = perceived_start;
+        self.rng = rng;
+        self.perception_stats = perception_stats;
+        self.time += dt;
+        if let Some(start) = step_start {
+            profile.total = start.elapsed();
+            self.profile = profile;
+        }
+        self.dispatch_events();
+    }
+
+    /// What the boid at `index` perceives, `None` when there is no such boid.
+    /// The simulation is left untouched, its random generator included.
+    pub fn perception(&self, index: usize) -> Option<BoidPerception> {
+        if index >= self.current.len() {
+            return None;
+        }
+        // the grid of the world is the one of the previous step
+        let mut grid = CellGrid::new();
+        grid.build(&self.current, self.parameters.max_radius());
+        let mut neighbours = Vec::new();
+        let mut perception_stats = PerceptionStats::default();
+        self.perceive(&grid, index, &mut neighbours, &mut perception_stats);
+        let mut steering = Steering::new();
+        self.compute_steering(index, &mut steering, &neighbours, &mut self.rng.clone());
+        neighbours.retain(|neighbour| neighbour.index != index);
+        Some(BoidPerception { neighbours, steering })
+    }
+
+    /// Fills `neighbours` with the boids perceived by the boid at `index`, itself included
+    fn perceive(
+        &self,
+        grid: &CellGrid,
+        index: usize,
+        neighbours: &mut Vec<Neighbour>,
+        perception_stats: &mut PerceptionStats,
+    ) {
+        self.find_neighbours(grid, index, neighbours);
+
+        if self.parameters.occlusion() {
+            perception_stats.nb_occluded += remove_occluded(
+                index,
+                &self.current.position(index),
+                neighbours,
+                self.parameters.bird_size(),
+                &self.obstacles,
+            );
+        }
+        perception_stats.nb_perceived += neighbours.len() - 1;
     }
 
     fn compute_steering(
         &self,
-        reference: Boid,
+        index: usize,
         steering: &mut Steering,
+        neighbours: &[Neighbour],
+        rng: &mut StdRng,
     ) -> bool {
-        let mut buffer = Vector { x: 0., y: 0. };
+        let flock = &self.current;
+        let position = flock.position(index);
+        let velocity = flock.velocity(index);
+        let mut buffer: Vector;
         steering.clear();
 
-        let mut nb_visible = 0;
-        let mut nb_in_safe_space = 0;
-        for boid in self.current.iter() {
-            let visibility = self.compute_separation(reference, *boid, &mut buffer);
-            if (visibility & IN_SAFE_SPACE) != 0 {
+        let separation = self.parameters.separation();
+        let alignment = self.parameters.alignment();
+        let cohesion = self.parameters.cohesion();
+        let mut nb_separation = 0;
+        let mut nb_alignment = 0;
+        let mut nb_cohesion = 0;
+        let mut alignment_weight = 0.0;
+        let mut cohesion_weight = 0.0;
+        for neighbour in neighbours.iter() {
+            let visibility = neighbour.visibility;
+            buffer = neighbour.offset;
+            if (visibility & IN_SEPARATION_RANGE) != 0 {
                 let norm2 = buffer.norm();
                 if norm2<DEFAULT_BIRD_SIZE*0.1 {
-                    buffer.set_random(DEFAULT_BIRD_MIN_SPEED)
+                    buffer.set_random(DEFAULT_BIRD_MIN_SPEED, rng)
                 }
-                nb_in_safe_space += 1;
-                steering.separation.add(&buffer);
+                nb_separation += 1;
+                steering
+                    .separation
+                    .add_scaled(&buffer, separation.weight(neighbour.distance));
             }
-            if (visibility & VISIBLE) != 0 {
-                nb_visible += 1;
-                steering.alignment.add(&boid.velocity);
-                steering.cohesion.add(&boid.position);
+            if (visibility & IN_ALIGNMENT_RANGE) != 0 {
+                let weight = alignment.weight(neighbour.distance);
+                nb_alignment += 1;
+                alignment_weight += weight;
+                steering.alignment.x += flock.vx[neighbour.index] * weight;
+                steering.alignment.y += flock.vy[neighbour.index] * weight;
+            }
+            if (visibility & IN_COHESION_RANGE) != 0 {
+                let weight = cohesion.weight(neighbour.distance);
+                nb_cohesion += 1;
+                cohesion_weight += weight;
+                steering.cohesion.x += flock.x[neighbour.index] * weight;
+                steering.cohesion.y += flock.y[neighbour.index] * weight;
             }
         }
-        //remove myself
-        nb_visible -= 1;
-        nb_in_safe_space -= 1;
-        steering.alignment.subtract(&reference.velocity);
-        steering.cohesion.subtract(&reference.position);
-
-
-        if nb_visible > 0 {
-            steering.alignment.scale(1. / (nb_visible as f32));
-            steering.cohesion.scale(1. / (nb_visible as f32));
-            steering.cohesion.subtract(&reference.position);
-            return true;
+        //remove myself (every kernel weights a distance of 0 by 1)
+        nb_separation -= 1;
+        nb_alignment -= 1;
+        nb_cohesion -= 1;
+        alignment_weight -= 1.0;
+        cohesion_weight -= 1.0;
+        steering.alignment.subtract(&velocity);
+        steering.cohesion.subtract(&position);
+
+        if nb_alignment > 0 && alignment_weight > 0.0 {
+            steering.alignment.scale(1. / alignment_weight);
+            steering.alignment.subtract(&velocity);
+        } else {
+            steering.alignment.clear();
         }
-        return nb_in_safe_space > 0;
-    }
-
-    fn compute_separation(&self, reference: Boid, other: Boid, separation: &mut Vector) -> u8 {
-        let visibility_radius = self.parameters.visibility_radius;
-        *separation = reference.position;
-        separation.subtract(&other.position);
 
-        if separation.x.abs() > visibility_radius || separation.y.abs() > visibility_radius {
-            return NOT_VISIBLE;
+        if nb_cohesion > 0 && cohesion_weight > 0.0 {
+            steering.cohesion.scale(1. / cohesion_weight);
+            steering.cohesion.subtract(&position);
+        } else {
+            steering.cohesion.clear();
         }
 
-        let distance = separation.hypot();
-        if distance > visibility_radius {
-            return NOT_VISIBLE;
-        }
-        let prod = (separation.x * reference.velocity.x + separation.y * reference.velocity.y)
-            / (distance * reference.speed());
-        if prod < self.parameters.cos_max_angle {
-            return NOT_VISIBLE;
-        }
+        nb_separation > 0 || nb_alignment > 0 || nb_cohesion > 0
+    }
 
-        if distance < visibility_radius * self.parameters.safe_space_ratio {
-            return IN_SAFE_SPACE | VISIBLE;
-        }
+    /// Fills `neighbours` with the boids perceived by at least one rule from the boid at `index`
+    /// (itself included). Only the boids of the cells of `grid` around the boid are considered,
+    /// `grid` being built with cells at least as large as the largest perception radius.
+    fn find_neighbours(&self, grid: &CellGrid, index: usize, neighbours: &mut Vec<Neighbour>) {
+        let max_radius = self.parameters.max_radius();
+        let max_radius2 = max_radius * max_radius;
+        let perceptions = [
+            (self.parameters.separation(), IN_SEPARATION_RANGE),
+            (self.parameters.alignment(), IN_ALIGNMENT_RANGE),
+            (self.parameters.cohesion(), IN_COHESION_RANGE),
+        ];
+        let flock = &self.current;
+        let (rx, ry) = (flock.x[index], flock.y[index]);
+        let (vx, vy) = (flock.vx[index], flock.vy[index]);
+        let speed = flock.speed[index];
+
+        neighbours.clear();
+        grid.for_each_candidate(rx, ry, |j| {
+            let separation = Vector { x: rx - flock.x[j], y: ry - flock.y[j] };
+            let distance2 = separation.x * separation.x + separation.y * separation.y;
+            if distance2 > max_radius2 {
+                return;
+            }
+            let distance = distance2.sqrt();
+            let prod = (separation.x * vx + separation.y * vy) / (distance * speed);
 
-        VISIBLE
+            let mut visibility = 0;
+            for (perception, flag) in perceptions.iter() {
+                if distance > perception.radius() || prod < perception.cos_max_angle() {
+                    continue;
+                }
+                visibility |= flag;
+            }
+            if visibility != 0 {
+                neighbours.push(Neighbour::new(j, separation, visibility));
+            }
+        });
     }
 }
 
 fn constraint_boid_rect(boid: &mut Boid, playfield_size: f32) {
     let var = CONSTRAINT_STRENGTH;
-    let limitx = playfield_size * 0.9;
-    let limity = playfield_size * 0.8;
+    let limitx = playfield_size * CONSTRAINT_LIMIT_X;
+    let limity = playfield_size * CONSTRAINT_LIMIT_Y;
 
     if boid.position.x > limitx {
         boid.velocity.x -= var * (boid.position.x - limitx);
@@ -248,3 +593,12 @@ fn constraint_boid_rect(boid: &mut Boid, playfield_size: f32) {
         boid.velocity.x -= 0.1;
     }
 }
+
+fn is_beyond(position: &Vector, side: Side, playfield_size: f32) -> bool {
+    match side {
+        Side::Left => position.x < -playfield_size * CONSTRAINT_LIMIT_X,
+        Side::Right => position.x > playfield_size * CONSTRAINT_LIMIT_X,
+        Side::Bottom => position.y < -playfield_size * CONSTRAINT_LIMIT_Y,
+        Side::Top => position.y > playfield_size * CONSTRAINT_LIMIT_Y,
+    }
+}
@@ -0,0 +1,198 @@
+use raylib::prelude::*;
+
+use crate::data::boid::Boid;
+use crate::data::density::DensityGrid;
+use crate::data::flock::Flock;
+use crate::data::metrics::BoidMetric;
+use crate::data::neighbour::{IN_ALIGNMENT_RANGE, IN_COHESION_RANGE, IN_SEPARATION_RANGE};
+use crate::data::trails::Trails;
+use crate::data::world::World;
+use crate::render::canvas::Rgb;
+use crate::render::colour::{metric_colour, CLUSTER_COLORS, OTHER_CLUSTER_COLOR};
+use crate::render::heatmap::{cell_colour, HEATMAP_OPACITY};
+use crate::render::perception::{cone_arc, rule_overlays, CONE_OPACITY, LINE_WIDTH, NEIGHBOUR_COLOR, SEPARATION_COLOR};
+use crate::render::scene::{SELECTED_COLOR, TRAIL_OPACITY, TRAIL_WIDTH};
+use crate::{toggle_text, ScreenSize};
+
+pub fn draw_birds(d: &mut impl RaylibDraw, flock: &Flock, bird_size: f32, colours: &[Rgb]) {
+    for (boid, [r, g, b]) in flock.iter().zip(colours.iter()) {
+        draw_bird(d, &boid, bird_size, Color::new(*r, *g, *b, 255));
+    }
+}
+
+/// Legend of the colours of the boids for `metric`, whose colours are spread over `range`,
+/// drawn in the bottom right corner of the screen
+pub fn draw_legend(d: &mut impl RaylibDraw, metric: BoidMetric, range: (f32, f32), screen_size: &ScreenSize) {
+    let (width, height) = (220, 64);
+    let x = screen_size.width - width - 10;
+    let y = screen_size.height - height - 10;
+    let to_color = |[r, g, b]: Rgb| Color::new(r, g, b, 255);
+    d.draw_rectangle(x, y, width, height, Color::WHITE.fade(0.8));
+    d.draw_rectangle_lines(x, y, width, height, Color::BLACK);
+
+    let bar_x = x + 10;
+    let bar_y = y + 26;
+    let bar_width = width - 20;
+    let (min, max) = range;
+    let (title, min_text, max_text) = match metric {
+        BoidMetric::Speed => ("Speed".to_string(), format!("{:.1}", min), format!("{:.1}", max)),
+        BoidMetric::Heading => ("Heading (degrees)".to_string(), "-180".to_string(), "180".to_string()),
+        BoidMetric::Neighbours => ("Neighbours".to_string(), format!("{}", min), format!("{}", max)),
+        BoidMetric::Cluster => (
+            "Cluster, largest first".to_string(),
+            "1".to_string(),
+            format!("{} / other", CLUSTER_COLORS.len()),
+        ),
+    };
+    d.draw_text(&title, bar_x, y + 6, 16, Color::BLACK);
+
+    if metric == BoidMetric::Cluster {
+        let swatch_width = bar_width / (CLUSTER_COLORS.len() as i32 + 1);
+        for (index, colour) in CLUSTER_COLORS.iter().chain([OTHER_CLUSTER_COLOR].iter()).enumerate() {
+            d.draw_rectangle(bar_x + index as i32 * swatch_width, bar_y, swatch_width - 2, 14, to_color(*colour));
+        }
+    } else {
+        let nb_steps = 50;
+        for step in 0..nb_steps {
+            let value = min + (max - min) * (step as f32 + 0.5) / nb_steps as f32;
+            let colour = to_color(metric_colour(metric, value, range));
+            let left = bar_x + bar_width * step / nb_steps;
+            let right = bar_x + bar_width * (step + 1) / nb_steps;
+            d.draw_rectangle(left, bar_y, right - left, 14, colour);
+        }
+    }
+    d.draw_text(&min_text, bar_x, bar_y + 18, 14, Color::BLACK);
+    let max_width = measure_text(&max_text, 14);
+    d.draw_text(&max_text, bar_x + bar_width - max_width, bar_y + 18, 14, Color::BLACK);
+}
+
+fn draw_bird(d: &mut impl RaylibDraw, boid: &Boid, bird_size: f32, color: Color) {
+    let [head, left_wing, right_wing] = boid.triangle(bird_size);
+    d.draw_triangle(
+        Vector2 { x: head.x, y: head.y },
+        Vector2 { x: left_wing.x, y: left_wing.y },
+        Vector2 { x: right_wing.x, y: right_wing.y },
+        color,
+    );
+}
+
+pub fn draw_trails(d: &mut impl RaylibDraw, trails: &Trails, bird_size: f32) {
+    let length = trails.length() as f32;
+    for index in 0..trails.nb_birds() {
+        let points: Vec<Vector2> = trails.trail(index).map(|p| Vector2 { x: p.x, y: p.y }).collect();
+        let nb_segments = points.len().saturating_sub(1) as f32;
+        for (k, segment) in points.windows(2).enumerate() {
+            let age = nb_segments - k as f32;
+            let color = Color::BLACK.fade(TRAIL_OPACITY * (1.0 - age / length));
+            d.draw_line_ex(segment[0], segment[1], bird_size * TRAIL_WIDTH, color);
+        }
+    }
+}
+
+pub fn draw_density(d: &mut impl RaylibDraw, grid: &DensityGrid) {
+    let max = grid.max();
+    let cell_size = grid.cell_size();
+    for row in 0..grid.resolution() {
+        for column in 0..grid.resolution() {
+            if let Some(([r, g, b], opacity)) = cell_colour(grid.value(column, row), max) {
+                d.draw_rectangle_v(
+                    Vector2 {
+                        x: -grid.half_size() + column as f32 * cell_size,
+                        y: -grid.half_size() + row as f32 * cell_size,
+                    },
+                    Vector2 { x: cell_size, y: cell_size },
+                    Color::new(r, g, b, 255).fade(HEATMAP_OPACITY * opacity),
+                );
+            }
+        }
+    }
+}
+
+/// Draws what the boid at `index` perceives, as the software renderer does
+pub fn draw_perception(d: &mut impl RaylibDraw, world: &World, index: usize) {
+    let perception = match world.perception(index) {
+        Some(perception) => perception,
+        None => return,
+    };
+    let to_color = |[r, g, b]: Rgb| Color::new(r, g, b, 255);
+    let boid = world.current.boid(index);
+    let center = Vector2 { x: boid.position.x, y: boid.position.y };
+    let heading = boid.velocity.y.atan2(boid.velocity.x);
+    let width = world.parameters.bird_size() * LINE_WIDTH;
+    let overlays = rule_overlays(&world.parameters, &perception.steering);
+
+    for rule in overlays.iter() {
+        let color = to_color(rule.color).fade(CONE_OPACITY);
+        let arc = cone_arc(&boid.position, heading, rule.dead_half_angle, rule.radius);
+        for segment in arc.windows(2) {
+            let (a, b) = (Vector2 { x: segment[0].x, y: segment[0].y }, Vector2 { x: segment[1].x, y: segment[1].y });
+            // raylib only draws the triangles wound as the ones of the birds
+            if (a.x - center.x) * (b.y - center.y) - (a.y - center.y) * (b.x - center.x) > 0.0 {
+                d.draw_triangle(center, b, a, color);
+            } else {
+                d.draw_triangle(center, a, b, color);
+            }
+        }
+    }
+    for rule in overlays.iter() {
+        let (inner, outer) = ((rule.radius - width * 0.5).max(0.0), rule.radius + width * 0.5);
+        d.draw_ring(center, inner, outer, 0, 360, 64, to_color(rule.color));
+    }
+    for neighbour in perception.neighbours.iter() {
+        let color = if neighbour.visibility & IN_SEPARATION_RANGE != 0 { SEPARATION_COLOR } else { NEIGHBOUR_COLOR };
+        let position = world.current.position(neighbour.index);
+        d.draw_line_ex(center, Vector2 { x: position.x, y: position.y }, width, to_color(color).fade(0.8));
+    }
+    for rule in overlays.iter() {
+        let end = Vector2 { x: center.x + rule.arrow.x, y: center.y + rule.arrow.y };
+        d.draw_line_ex(center, end, 2.0 * width, to_color(rule.color));
+    }
+}
+
+/// Draws the selected boid over the others, circled
+pub fn draw_selection(d: &mut impl RaylibDraw, boid: &Boid, bird_size: f32) {
+    let [r, g, b] = SELECTED_COLOR;
+    let color = Color::new(r, g, b, 255);
+    let center = Vector2 { x: boid.position.x, y: boid.position.y };
+    d.draw_ring(center, bird_size * 1.8, bird_size * 2.0, 0, 360, 32, color);
+    draw_bird(d, boid, bird_size, color);
+}
+
+/// Panel showing the state of the boid at `index`, drawn in the top right corner of the screen
+pub fn draw_inspector(d: &mut impl RaylibDraw, world: &World, index: usize, following: bool, screen_size: &ScreenSize) {
+    let boid = world.current.boid(index);
+    let neighbours = world.perception(index).map(|perception| perception.neighbours).unwrap_or_default();
+    let count = |flag: u8| neighbours.iter().filter(|neighbour| neighbour.visibility & flag != 0).count();
+    let lines = [
+        format!("Boid {}", index),
+        format!("Position: {:.2}, {:.2}", boid.position.x, boid.position.y),
+        format!("Velocity: {:.2}, {:.2}", boid.velocity.x, boid.velocity.y),
+        format!("Speed: {:.2}", boid.speed()),
+        format!("Perceived: {}", neighbours.len()),
+        format!("  separation: {}", count(IN_SEPARATION_RANGE)),
+        format!("  alignment: {}", count(IN_ALIGNMENT_RANGE)),
+        format!("  cohesion: {}", count(IN_COHESION_RANGE)),
+        toggle_text("Follow (F)", following),
+    ];
+    let (width, height) = (220, 12 + 20 * lines.len() as i32);
+    let x = screen_size.width - width - 10;
+    let y = 30;
+    d.draw_rectangle(x, y, width, height, Color::WHITE.fade(0.8));
+    d.draw_rectangle_lines(x, y, width, height, Color::BLACK);
+    for (k, line) in lines.iter().enumerate() {
+        d.draw_text(line, x + 10, y + 8 + 20 * k as i32, 16, Color::BLACK);
+    }
+}
+
+pub fn draw_obstacles_and_predators(d: &mut impl RaylibDraw, world: &World) {
+    for obstacle in world.obstacles.iter() {
+        d.draw_circle_v(
+            Vector2 { x: obstacle.center.x, y: obstacle.center.y },
+            obstacle.radius,
+            Color::GRAY,
+        );
+    }
+    for predator in world.predators.iter() {
+        draw_bird(d, predator, world.parameters.bird_size() * 2.0, Color::RED);
+    }
+}
@@ -0,0 +1,239 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::data::parameters::Parameters;
+use crate::data::preset::load_preset;
+use crate::data::world::World;
+use crate::headless::options::Options;
+use crate::{DEFAULT_NB_BIRDS, DEFAULT_WORLD_SIZE};
+
+pub const BENCHMARK_OPTIONS: [&str; 10] = [
+    "sizes",
+    "steps",
+    "warmup",
+    "dt",
+    "world-size",
+    "seed",
+    "max-step-time",
+    "preset",
+    "format",
+    "output",
+];
+
+const DEFAULT_SIZES: [usize; 4] = [1_000, 10_000, 100_000, 1_000_000];
+
+struct BenchmarkResult {
+    nb_birds: usize,
+    world_size: f32,
+    /// number of measured steps, 0 when the size was skipped
+    nb_steps: usize,
+    /// extrapolated duration of a step, in seconds, when the size was skipped
+    estimated_step_time: Option<f64>,
+    total: Duration,
+    neighbour_search: Duration,
+    integration: Duration,
+}
+
+impl BenchmarkResult {
+    fn is_skipped(&self) -> bool {
+        self.nb_steps == 0
+    }
+
+    fn steps_per_second(&self) -> f64 {
+        self.nb_steps as f64 / self.total.as_secs_f64()
+    }
+
+    fn step_time(&self) -> f64 {
+        self.total.as_secs_f64() / self.nb_steps as f64
+    }
+}
+
+/// Measures the duration of `World::compute` for increasing flock sizes.
+///
+/// The playfield grows with the flock so that the density, and hence the number of
+/// neighbours per boid, stays the one of the application, unless `--world-size` is given.
+/// A size whose step would take more than `--max-step-time` seconds, extrapolated from
+/// the previous size, is not run and is reported as skipped in the output, with the
+/// extrapolated step time. The cost of a step grows with the number of boids times their
+/// number of neighbours, which is constant when the playfield grows with the flock.
+pub fn run(options: &Options) -> Result<(), String> {
+    let sizes: Vec<usize> = options.get_counts("sizes")?.unwrap_or_else(|| DEFAULT_SIZES.to_vec());
+    let nb_steps: usize = options.get_or("steps", 10)?;
+    let nb_warmup_steps: usize = options.get_or("warmup", 1)?;
+    let dt: f32 = options.get_dt()?;
+    let fixed_world_size: Option<f32> = options.get("world-size")?;
+    let seed: u64 = options.get_or("seed", 0)?;
+    let max_step_time: f64 = options.get_or("max-step-time", 30.0)?;
+    let parameters = match options.get_str("preset") {
+        None => Parameters::new(),
+        Some(path) => load_preset(path)?,
+    };
+    let format = options.get_str("format").unwrap_or("csv");
+    if format != "csv" && format != "json" {
+        return Err(format!("Unknown format '{}', expected 'csv' or 'json'", format));
+    }
+
+    let mut results: Vec<BenchmarkResult> = Vec::with_capacity(sizes.len());
+    for nb_birds in sizes {
+        let world_size = fixed_world_size.unwrap_or_else(|| {
+            DEFAULT_WORLD_SIZE * (nb_birds as f32 / DEFAULT_NB_BIRDS as f32).sqrt()
+        });
+
+        let estimated_step_time = results.iter().rev().find(|result| !result.is_skipped()).map(|result| {
+            let ratio = nb_birds as f64 / result.nb_birds as f64;
+            let density_ratio = ratio * (result.world_size as f64 / world_size as f64).powi(2);
+            result.step_time() * ratio * density_ratio
+        });
+        if let Some(estimate) = estimated_step_time.filter(|estimate| *estimate > max_step_time) {
+            eprintln!(
+                "{} boids: skipped, a step would take about {:.3}s, more than --max-step-time {}s",
+                nb_birds, estimate, max_step_time
+            );
+            results.push(BenchmarkResult {
+                nb_birds,
+                world_size,
+                nb_steps: 0,
+                estimated_step_time: Some(estimate),
+                total: Duration::ZERO,
+                neighbour_search: Duration::ZERO,
+                integration: Duration::ZERO,
+            });
+            continue;
+        }
+
+        let result = measure(&parameters, nb_birds, world_size, nb_steps.max(1), nb_warmup_steps, dt, seed);
+        eprintln!(
+            "{} boids: {:.2} steps/s, {:.0}% in neighbour search",
+            nb_birds,
+            result.steps_per_second(),
+            100.0 * result.neighbour_search.as_secs_f64() / result.total.as_secs_f64()
+        );
+        results.push(result);
+    }
+
+    let mut writer: Box<dyn Write> = match options.get_str("output") {
+        None => Box::new(std::io::stdout()),
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).map_err(|e| format!("Cannot create '{}': {}", path, e))?,
+        )),
+    };
+    let written = if format == "json" {
+        write_json(&mut writer, &results, max_step_time)
+    } else {
+        write_csv(&mut writer, &results, max_step_time)
+    };
+    written.map_err(|e| format!("Cannot write the benchmark results: {}", e))
+}
+
+fn measure(
+    parameters: &Parameters,
+    nb_birds: usize,
+    world_size: f32,
+    nb_steps: usize,
+    nb_warmup_steps: usize,
+    dt: f32,
+    seed: u64,
+) -> BenchmarkResult {
+    let mut world = World::new(nb_birds, world_size);
+    world.parameters = parameters.clone();
+    world.seed(seed);
+    world.initialize();
+    for _ in 0..nb_warmup_steps {
+        world.compute(dt);
+    }
+
+    world.set_profiling(true);
+    let mut result = BenchmarkResult {
+        nb_birds,
+        world_size,
+        nb_steps,
+        estimated_step_time: None,
+        total: Duration::ZERO,
+        neighbour_search: Duration::ZERO,
+        integration: Duration::ZERO,
+    };
+    for _ in 0..nb_steps {
+        world.compute(dt);
+        result.total += world.profile.total;
+        result.neighbour_search += world.profile.neighbour_search;
+        result.integration += world.profile.integration();
+    }
+    result
+}
+
+fn skip_reason(result: &BenchmarkResult, max_step_time: f64) -> String {
+    format!(
+        "estimated step time of {:.3}s exceeds --max-step-time {}s",
+        result.estimated_step_time.unwrap_or(f64::NAN),
+        max_step_time
+    )
+}
+
+fn write_csv(writer: &mut dyn Write, results: &[BenchmarkResult], max_step_time: f64) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "version,boids,world_size,steps,status,total_s,steps_per_s,neighbour_search_s,integration_s,reason"
+    )?;
+    for result in results {
+        if result.is_skipped() {
+            writeln!(
+                writer,
+                "{},{},{},0,skipped,,,,,{}",
+                env!("CARGO_PKG_VERSION"),
+                result.nb_birds,
+                result.world_size,
+                skip_reason(result, max_step_time)
+            )?;
+        } else {
+            writeln!(
+                writer,
+                "{},{},{},{},measured,{},{},{},{},",
+                env!("CARGO_PKG_VERSION"),
+                result.nb_birds,
+                result.world_size,
+                result.nb_steps,
+                result.total.as_secs_f64(),
+                result.steps_per_second(),
+                result.neighbour_search.as_secs_f64(),
+                result.integration.as_secs_f64()
+            )?;
+        }
+    }
+    writer.flush()
+}
+
+fn write_json(writer: &mut dyn Write, results: &[BenchmarkResult], max_step_time: f64) -> std::io::Result<()> {
+    let results: Vec<Value> = results
+        .iter()
+        .map(|result| {
+            if result.is_skipped() {
+                json!({
+                    "boids": result.nb_birds,
+                    "world_size": result.world_size,
+                    "steps": 0,
+                    "status": "skipped",
+                    "estimated_step_s": result.estimated_step_time,
+                    "reason": skip_reason(result, max_step_time),
+                })
+            } else {
+                json!({
+                    "boids": result.nb_birds,
+                    "world_size": result.world_size,
+                    "steps": result.nb_steps,
+                    "status": "measured",
+                    "total_s": result.total.as_secs_f64(),
+                    "steps_per_s": result.steps_per_second(),
+                    "neighbour_search_s": result.neighbour_search.as_secs_f64(),
+                    "integration_s": result.integration.as_secs_f64(),
+                })
+            }
+        })
+        .collect();
+    let document = json!({ "version": env!("CARGO_PKG_VERSION"), "results": results });
+    serde_json::to_writer_pretty(&mut *writer, &document)?;
+    writeln!(writer)?;
+    writer.flush()
+}
@@ -0,0 +1,109 @@
+use crate::data::parameters::{Parameters, ParametersBuilder};
+
+/// The parameters that can be swept or fitted, i.e. the ones the sliders control
+#[derive(Copy, Clone)]
+pub enum ParameterField {
+    Alignment,
+    Cohesion,
+    Separation,
+    SeparationRadius,
+    AlignmentRadius,
+    CohesionRadius,
+    SeparationDeadAngle,
+    AlignmentDeadAngle,
+    CohesionDeadAngle,
+    LongRange,
+}
+
+pub const PARAMETER_FIELDS: [ParameterField; 10] = [
+    ParameterField::Alignment,
+    ParameterField::Cohesion,
+    ParameterField::Separation,
+    ParameterField::SeparationRadius,
+    ParameterField::AlignmentRadius,
+    ParameterField::CohesionRadius,
+    ParameterField::SeparationDeadAngle,
+    ParameterField::AlignmentDeadAngle,
+    ParameterField::CohesionDeadAngle,
+    ParameterField::LongRange,
+];
+
+impl ParameterField {
+    /// name of the command line option
+    pub fn option_name(&self) -> &'static str {
+        match self {
+            ParameterField::Alignment => "alignment",
+            ParameterField::Cohesion => "cohesion",
+            ParameterField::Separation => "separation",
+            ParameterField::SeparationRadius => "separation-radius",
+            ParameterField::AlignmentRadius => "alignment-radius",
+            ParameterField::CohesionRadius => "cohesion-radius",
+            ParameterField::SeparationDeadAngle => "separation-dead-angle",
+            ParameterField::AlignmentDeadAngle => "alignment-dead-angle",
+            ParameterField::CohesionDeadAngle => "cohesion-dead-angle",
+            ParameterField::LongRange => "long-range",
+        }
+    }
+
+    /// name of the field in the presets and in the result tables
+    pub fn column_name(&self) -> &'static str {
+        match self {
+            ParameterField::Alignment => "alignment_factor",
+            ParameterField::Cohesion => "cohesion_factor",
+            ParameterField::Separation => "separation_factor",
+            ParameterField::SeparationRadius => "separation_radius",
+            ParameterField::AlignmentRadius => "alignment_radius",
+            ParameterField::CohesionRadius => "cohesion_radius",
+            ParameterField::SeparationDeadAngle => "separation_dead_angle",
+            ParameterField::AlignmentDeadAngle => "alignment_dead_angle",
+            ParameterField::CohesionDeadAngle => "cohesion_dead_angle",
+            ParameterField::LongRange => "long_range_factor",
+        }
+    }
+
+    /// range of the matching slider, in parameter units
+    pub fn range(&self) -> (f32, f32) {
+        match self {
+            ParameterField::Alignment
+            | ParameterField::Cohesion
+            | ParameterField::Separation
+            | ParameterField::LongRange => (0.0, 1.0),
+            ParameterField::SeparationRadius
+            | ParameterField::AlignmentRadius
+            | ParameterField::CohesionRadius => (0.05, 2.0),
+            ParameterField::SeparationDeadAngle
+            | ParameterField::AlignmentDeadAngle
+            | ParameterField::CohesionDeadAngle => (0.0, 180.0),
+        }
+    }
+
+    pub fn get(&self, parameters: &Parameters) -> f32 {
+        match self {
+            ParameterField::Alignment => parameters.alignment_factor(),
+            ParameterField::Cohesion => parameters.cohesion_factor(),
+            ParameterField::Separation => parameters.separation_factor(),
+            ParameterField::SeparationRadius => parameters.separation().radius(),
+            ParameterField::AlignmentRadius => parameters.alignment().radius(),
+            ParameterField::CohesionRadius => parameters.cohesion().radius(),
+            ParameterField::SeparationDeadAngle => parameters.separation().dead_angle(),
+            ParameterField::AlignmentDeadAngle => parameters.alignment().dead_angle(),
+            ParameterField::CohesionDeadAngle => parameters.cohesion().dead_angle(),
+            ParameterField::LongRange => parameters.long_range_factor(),
+        }
+    }
+
+    pub fn apply(&self, builder: &mut ParametersBuilder, value: f32) {
+        match self {
+            ParameterField::Alignment => builder.set_alignment_factor(value),
+            ParameterField::Cohesion => builder.set_cohesion_factor(value),
+            ParameterField::Separation => builder.set_separation_factor(value),
+            ParameterField::SeparationRadius => builder.set_separation_radius(value),
+            ParameterField::AlignmentRadius => builder.set_alignment_radius(value),
+            ParameterField::CohesionRadius => builder.set_cohesion_radius(value),
+            ParameterField::SeparationDeadAngle => builder.set_separation_dead_angle(value),
+            ParameterField::AlignmentDeadAngle => builder.set_alignment_dead_angle(value),
+            ParameterField::CohesionDeadAngle => builder.set_cohesion_dead_angle(value),
+            ParameterField::LongRange => builder.set_long_range_factor(value),
+        };
+    }
+}
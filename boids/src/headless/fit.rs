@@ -0,0 +1,169 @@
+use std::sync::Arc;
+use std::thread;
+
+use crate::data::metrics::FlockMetrics;
+use crate::data::parameters::Parameters;
+use crate::data::preset::{load_preset, save_preset};
+use crate::data::scenario::load_scenario;
+use crate::data::script::load_script;
+use crate::headless::field::PARAMETER_FIELDS;
+use crate::headless::nelder_mead::minimize;
+use crate::headless::options::Options;
+use crate::headless::simulation::{run_simulation, RunSettings};
+use crate::DEFAULT_WORLD_SIZE;
+
+pub const FIT_OPTIONS: [&str; 15] = [
+    "polarization",
+    "nearest-neighbour-distance",
+    "milling",
+    "evaluations",
+    "replicates",
+    "birds",
+    "world-size",
+    "steps",
+    "dt",
+    "average",
+    "seed",
+    "preset",
+    "scenario",
+    "script",
+    "output",
+];
+
+const DEFAULT_FIT_NB_BIRDS: usize = 500;
+
+struct Targets {
+    polarization: Option<f32>,
+    nearest_neighbour_distance: Option<f32>,
+    milling: Option<f32>,
+}
+
+impl Targets {
+    fn describe(&self) -> String {
+        let targets = [
+            ("polarization", self.polarization),
+            ("nearest neighbour distance", self.nearest_neighbour_distance),
+            ("milling", self.milling),
+        ];
+        targets
+            .iter()
+            .filter_map(|(name, target)| target.map(|value| format!("{} {}", name, value)))
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    /// sum of the squared relative errors of the targeted metrics
+    fn error(&self, metrics: &FlockMetrics) -> f32 {
+        let relative_error2 = |target: Option<f32>, value: f32| match target {
+            None => 0.0,
+            Some(target) => ((value - target) / target.abs().max(0.05)).powi(2),
+        };
+        relative_error2(self.polarization, metrics.polarization)
+            + relative_error2(self.nearest_neighbour_distance, metrics.nearest_neighbour_distance)
+            + relative_error2(self.milling, metrics.milling)
+    }
+}
+
+/// Searches, with a Nelder-Mead simplex over the slider parameters, the
+/// parameters whose headless runs match the target metrics best,
+/// and writes them as a preset
+pub fn run(options: &Options) -> Result<(), String> {
+    let targets = Targets {
+        polarization: options.get("polarization")?,
+        nearest_neighbour_distance: options.get("nearest-neighbour-distance")?,
+        milling: options.get("milling")?,
+    };
+    if targets.polarization.is_none()
+        && targets.nearest_neighbour_distance.is_none()
+        && targets.milling.is_none()
+    {
+        return Err("At least one target metric is required".to_string());
+    }
+
+    let nb_steps: usize = options.get_or("steps", 600)?;
+    let settings = RunSettings {
+        nb_birds: options.get_or("birds", DEFAULT_FIT_NB_BIRDS)?,
+        world_size: options.get_or("world-size", DEFAULT_WORLD_SIZE)?,
+        nb_steps,
+        dt: options.get_dt()?,
+        nb_averaging_steps: options.get_or("average", (nb_steps / 4).max(1))?,
+        scenario: options.get_str("scenario").map(load_scenario).transpose()?,
+        script: options.get_str("script").map(load_script).transpose()?.map(Arc::new),
+    };
+    let nb_replicates: usize = options.get_or("replicates", 2)?;
+    let max_evaluations: usize = options.get_or("evaluations", 100)?;
+    let base_seed: u64 = options.get_or("seed", 0)?;
+    let output = options.get_str("output").unwrap_or("fitted.preset");
+    let initial = match options.get_str("preset") {
+        None => Parameters::new(),
+        Some(path) => load_preset(path)?,
+    };
+
+    let to_parameters = |point: &[f32]| -> Parameters {
+        let mut builder = initial.to_builder();
+        for (field, coordinate) in PARAMETER_FIELDS.iter().zip(point.iter()) {
+            let (min, max) = field.range();
+            field.apply(&mut builder, min + coordinate.clamp(0.0, 1.0) * (max - min));
+        }
+        builder
+            .build()
+            .expect("the fitted fields are clamped to their valid range")
+    };
+    let evaluate = |parameters: &Parameters| -> FlockMetrics {
+        run_replicates(parameters, &settings, nb_replicates.max(1), base_seed)
+    };
+
+    let start: Vec<f32> = PARAMETER_FIELDS
+        .iter()
+        .map(|field| {
+            let (min, max) = field.range();
+            (field.get(&initial) - min) / (max - min)
+        })
+        .collect();
+
+    let mut nb_evaluations = 0;
+    let (best_point, best_error) = minimize(
+        |point| {
+            let metrics = evaluate(&to_parameters(point));
+            let error = targets.error(&metrics);
+            nb_evaluations += 1;
+            eprintln!("evaluation {}: error {:.5}", nb_evaluations, error);
+            error
+        },
+        &start,
+        0.2,
+        max_evaluations,
+        1e-4,
+    );
+
+    let best = to_parameters(&best_point);
+    let metrics = evaluate(&best);
+    let comments = vec![
+        "fitted by 'boids fit'".to_string(),
+        format!("targets: {}", targets.describe()),
+        format!(
+            "reached: polarization {}, nearest neighbour distance {}, milling {} (error {})",
+            metrics.polarization, metrics.nearest_neighbour_distance, metrics.milling, best_error
+        ),
+    ];
+    save_preset(&best, output, &comments)?;
+    eprintln!("best parameters written to '{}'", output);
+    Ok(())
+}
+
+/// Runs the replicates in parallel, each with its own seed, and averages their metrics
+fn run_replicates(parameters: &Parameters, settings: &RunSettings, nb_replicates: usize, base_seed: u64) -> FlockMetrics {
+    let mut metrics = FlockMetrics::default();
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..nb_replicates)
+            .map(|replicate| {
+                scope.spawn(move || run_simulation(parameters, settings, base_seed + replicate as u64))
+            })
+            .collect();
+        for handle in handles {
+            metrics.add(&handle.join().unwrap());
+        }
+    });
+    metrics.scale(1.0 / nb_replicates as f32);
+    metrics
+}
@@ -0,0 +1,174 @@
+use crate::headless::field::PARAMETER_FIELDS;
+use crate::headless::options::Options;
+
+pub mod benchmark;
+pub mod field;
+pub mod fit;
+pub mod nelder_mead;
+pub mod options;
+pub mod render;
+pub mod run;
+pub mod simulation;
+pub mod stream_client;
+pub mod svg;
+pub mod sweep;
+
+pub const USAGE: &str = "Usage:
+  boids [--preset FILE] [--scenario FILE] [--script FILE]
+        [--stream-port P] [--stream-decimation N] [--api-port P]
+        [--osc-port P] [--osc-map FILE] [--osc-bind A]
+        [--trail-length N] [--trail-decimation N]
+        [--density-resolution N] [--density-half-life T]
+                             start the interactive application
+  boids run [options]        run a single simulation without window
+  boids sweep [options]      run a parameter sweep without window
+  boids fit [options]        search the parameters matching target flock metrics
+  boids benchmark [options]  measure the simulation speed for several flock sizes
+  boids render [options]     render a simulation without window to PNG files or a GIF
+  boids svg [options]        write the state of a simulation run without window as an SVG image
+  boids stream-client [options]
+                             print the frames streamed by a running simulation
+
+Run options:
+  --birds, --world-size, --dt, --seed, --scenario, --script
+                             same as for the sweep
+  --steps N                  number of steps, 0 to run until interrupted (600)
+  --preset FILE              parameters to run with (default parameters)
+  --realtime true|false      wait between the steps to run at the speed of the application (false)
+  --stream-port P            stream the state of the simulation to WebSocket clients on
+                             ws://127.0.0.1:P (see src/network/stream.rs for the framing)
+  --stream-decimation N      stream one step out of N (1)
+  --density-output FILE      write the density of the boids accumulated during the run,
+                             as an image when FILE ends with .png, as a CSV grid otherwise
+  --density-resolution N     number of cells of the density grid along each side (64)
+  --density-half-life T      half-life in seconds of the density counts, 0 for no decay (0)
+  --api-port P               serve the remote control HTTP/JSON API on http://127.0.0.1:P
+                             (see src/network/api.rs for the endpoints)
+  --osc-port P               apply the Open Sound Control messages received on UDP port P
+  --osc-map FILE             bindings of the OSC addresses to the parameters and actions
+                             (see src/network/osc.rs for the format, /boids/<field> by default)
+  --osc-bind A               address the OSC listener is bound to, 0.0.0.0 for every
+                             interface (127.0.0.1)
+
+Sweep options:
+  --alignment, --cohesion, --separation,
+  --separation-radius, --alignment-radius, --cohesion-radius,
+  --separation-dead-angle, --alignment-dead-angle, --cohesion-dead-angle,
+  --long-range, --dead-angle, --safe-space-ratio
+                             swept values, either 'v1,v2,...' or a grid 'start:end:count'.
+                             The factors and radii are raw values (the sliders show them x100).
+                             --dead-angle sets the three rules, the per rule angles override it,
+                             --safe-space-ratio the separation radius relative to the alignment one
+  --replicates N             number of runs per point, each with its own seed (1)
+  --birds N                  number of boids (2000)
+  --world-size S             half size of the playfield (10)
+  --steps N                  number of steps per run (600)
+  --dt T                     duration of a step in seconds (1/60)
+  --average N                number of final steps the metrics are averaged over (steps/4)
+  --seed S                   seed of the first run, the following runs use S+1, S+2... (0)
+  --threads N                number of parallel runs (number of cpus)
+  --scenario FILE            scenario executed from the start of every run
+  --script FILE              Rhai script defining a custom steering rule
+  --output FILE              CSV file to write the results to (stdout)
+
+Fit options:
+  --polarization P, --nearest-neighbour-distance D, --milling M
+                             target metrics, at least one is required
+  --evaluations N            maximal number of evaluated parameter sets (100)
+  --replicates N             number of runs averaged per evaluation (2)
+  --birds N                  number of boids (500)
+  --world-size, --steps, --dt, --average, --seed, --scenario, --script
+                             same as for the sweep
+  --preset FILE              preset to start the search from (default parameters)
+  --output FILE              preset file to write the best parameters to (fitted.preset)
+
+Benchmark options:
+  --sizes N1,N2,...          numbers of boids (1000,10000,100000,1000000)
+  --steps N                  number of measured steps per size (10)
+  --warmup N                 number of steps run before measuring (1)
+  --world-size S             half size of the playfield (grows with the flock to keep
+                             the density of the application)
+  --max-step-time T          skip the sizes whose step would take more than T seconds,
+                             estimated from the previous size, and report them as
+                             skipped with the estimate in the results (30)
+  --dt, --seed               same as for the sweep
+  --preset FILE              parameters to run with (default parameters)
+  --format csv|json          format of the results (csv)
+  --output FILE              file to write the results to (stdout)
+
+Render options:
+  --birds, --world-size, --steps, --dt, --seed, --preset, --scenario, --script
+                             same as for the run
+  --output FILE              animated GIF when FILE ends with .gif, PNG files otherwise,
+                             numbered in place of '{}' or before the extension (boids.gif)
+  --every N                  render one step out of N, starting with the initial state (1)
+  --width W, --height H      size of the images in pixels (800, 600)
+  --center X,Y               point of the world at the centre of the images (0,0)
+  --view-size S              world units shown above and below the centre
+                             (the playfield fills 80% of the images when neither is given)
+  --trail-length N           draw the last N recorded positions of each boid, 0 for no trails (0)
+  --trail-decimation N       record the position of the boids every N steps (1)
+  --density true|false       draw the density of the boids behind them (false)
+  --density-resolution, --density-half-life
+                             same as for the run
+  --colour METRIC            same as for the SVG
+  --perception N             draw what the boid at index N perceives: range and dead angle
+                             cone of each rule, perceived neighbours, neighbours in the
+                             separation range in red, and the steering of each rule
+
+SVG options:
+  --birds, --world-size, --steps, --dt, --seed, --preset, --scenario, --script
+                             same as for the run, the image shows the last step
+  --width, --height, --center, --view-size
+                             same as for the render
+  --output FILE              SVG file to write (boids.svg)
+  --arrows true|false        draw the velocity of each boid as an arrow (false)
+  --boundary true|false      draw the rectangle the boids are steered back into (true)
+  --colour speed|heading|neighbours|cluster
+                             colour the boids by this metric (black), the neighbours and
+                             the clusters being computed with the cohesion radius
+
+Stream client options:
+  --port P                   port of the stream server (9001)
+  --frames N                 number of frames to print, 0 for no limit (0)";
+
+/// Runs the headless command described by `args` (the command line without the program name)
+pub fn run(args: &[String]) -> Result<(), String> {
+    match args[0].as_str() {
+        "run" => {
+            let options = Options::parse(&args[1..], &run::RUN_OPTIONS)?;
+            run::run(&options)
+        }
+        "sweep" => {
+            let mut known_options = sweep::SWEEP_OPTIONS.to_vec();
+            known_options.extend(PARAMETER_FIELDS.iter().map(|field| field.option_name()));
+            let options = Options::parse(&args[1..], &known_options)?;
+            sweep::run(&options)
+        }
+        "fit" => {
+            let options = Options::parse(&args[1..], &fit::FIT_OPTIONS)?;
+            fit::run(&options)
+        }
+        "benchmark" => {
+            let options = Options::parse(&args[1..], &benchmark::BENCHMARK_OPTIONS)?;
+            benchmark::run(&options)
+        }
+        "render" => {
+            let options = Options::parse(&args[1..], &render::RENDER_OPTIONS)?;
+            render::run(&options)
+        }
+        "svg" => {
+            let options = Options::parse(&args[1..], &svg::SVG_OPTIONS)?;
+            svg::run(&options)
+        }
+        "stream-client" => {
+            let options = Options::parse(&args[1..], &stream_client::STREAM_CLIENT_OPTIONS)?;
+            stream_client::run(&options)
+        }
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        command => Err(format!("Unknown command '{}'\n\n{}", command, USAGE)),
+    }
+}
@@ -0,0 +1,89 @@
+/// Minimises `objective` with the Nelder-Mead simplex method, starting from `start`
+/// with an initial simplex of size `step` along each axis.
+/// Stops after `max_evaluations` calls to `objective` or when the simplex values
+/// spread less than `tolerance`. Returns the best point found and its value.
+pub fn minimize<F>(
+    mut objective: F,
+    start: &[f32],
+    step: f32,
+    max_evaluations: usize,
+    tolerance: f32,
+) -> (Vec<f32>, f32)
+where
+    F: FnMut(&[f32]) -> f32,
+{
+    let dimension = start.len();
+    let mut simplex: Vec<(Vec<f32>, f32)> = Vec::with_capacity(dimension + 1);
+    simplex.push((start.to_vec(), objective(start)));
+    for axis in 0..dimension {
+        let mut point = start.to_vec();
+        point[axis] += step;
+        let value = objective(&point);
+        simplex.push((point, value));
+    }
+    let mut nb_evaluations = dimension + 1;
+
+    while nb_evaluations < max_evaluations {
+        simplex.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        if simplex[dimension].1 - simplex[0].1 < tolerance {
+            break;
+        }
+
+        let centroid: Vec<f32> = (0..dimension)
+            .map(|axis| simplex[..dimension].iter().map(|(p, _)| p[axis]).sum::<f32>() / dimension as f32)
+            .collect();
+        let towards = |coefficient: f32| -> Vec<f32> {
+            centroid
+                .iter()
+                .zip(simplex[dimension].0.iter())
+                .map(|(c, w)| c + coefficient * (w - c))
+                .collect()
+        };
+
+        let reflected = towards(-1.0);
+        let reflected_value = objective(&reflected);
+        nb_evaluations += 1;
+
+        if reflected_value < simplex[0].1 {
+            let expanded = towards(-2.0);
+            let expanded_value = objective(&expanded);
+            nb_evaluations += 1;
+            simplex[dimension] = if expanded_value < reflected_value {
+                (expanded, expanded_value)
+            } else {
+                (reflected, reflected_value)
+            };
+            continue;
+        }
+
+        if reflected_value < simplex[dimension - 1].1 {
+            simplex[dimension] = (reflected, reflected_value);
+            continue;
+        }
+
+        let contracted = if reflected_value < simplex[dimension].1 {
+            towards(-0.5)
+        } else {
+            towards(0.5)
+        };
+        let contracted_value = objective(&contracted);
+        nb_evaluations += 1;
+        if contracted_value < simplex[dimension].1.min(reflected_value) {
+            simplex[dimension] = (contracted, contracted_value);
+            continue;
+        }
+
+        // shrink towards the best point
+        let best = simplex[0].0.clone();
+        for (point, value) in simplex.iter_mut().skip(1) {
+            for (coordinate, b) in point.iter_mut().zip(best.iter()) {
+                *coordinate = b + 0.5 * (*coordinate - b);
+            }
+            *value = objective(point);
+            nb_evaluations += 1;
+        }
+    }
+
+    simplex.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+    simplex.swap_remove(0)
+}
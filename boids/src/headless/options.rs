@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// duration of a step when `--dt` is not given, the one of a frame of the application
+const DEFAULT_DT: f32 = 1.0 / 60.0;
+
+/// Command line options of the headless commands, given as `--name value` pairs
+pub struct Options {
+    values: HashMap<String, String>,
+}
+
+impl Options {
+    pub fn parse(args: &[String], known_options: &[&str]) -> Result<Self, String> {
+        let mut values = HashMap::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let name = arg
+                .strip_prefix("--")
+                .ok_or_else(|| format!("Expected an option starting with '--' but got '{}'", arg))?;
+            if !known_options.contains(&name) {
+                return Err(format!("Unknown option '--{}'", name));
+            }
+            let value = iter
+                .next()
+                .ok_or_else(|| format!("Missing value for option '--{}'", name))?;
+            values.insert(name.to_string(), value.clone());
+        }
+        Ok(Options { values })
+    }
+
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|value| value.as_str())
+    }
+
+    pub fn get<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+        self.values
+            .get(name)
+            .map(|value| parse_value(name, value))
+            .transpose()
+    }
+
+    pub fn get_or<T: FromStr>(&self, name: &str, default: T) -> Result<T, String> {
+        self.get(name).map(|value| value.unwrap_or(default))
+    }
+
+    /// Duration of a step in seconds given by `--dt`, which must be finite and positive
+    pub fn get_dt(&self) -> Result<f32, String> {
+        let dt = self.get_or("dt", DEFAULT_DT)?;
+        if dt.is_finite() && dt > 0.0 {
+            Ok(dt)
+        } else {
+            Err(format!("Invalid value '{}' for option '--dt': the duration of a step must be finite and positive", dt))
+        }
+    }
+
+    /// Parses a list of positive counts given as `n1,n2,n3`
+    pub fn get_counts(&self, name: &str) -> Result<Option<Vec<usize>>, String> {
+        self.values
+            .get(name)
+            .map(|value| {
+                value
+                    .split(',')
+                    .map(|item| match parse_value(name, item.trim())? {
+                        0 => Err(format!("Invalid value '{}' for option '--{}': counts must be positive", item, name)),
+                        count => Ok(count),
+                    })
+                    .collect::<Result<Vec<usize>, String>>()
+            })
+            .transpose()
+    }
+
+    /// Parses a list of values given either as `v1,v2,v3` or as a grid `start:end:count`
+    pub fn get_list(&self, name: &str) -> Result<Option<Vec<f32>>, String> {
+        let value = match self.values.get(name) {
+            None => return Ok(None),
+            Some(value) => value,
+        };
+
+        let grid: Vec<&str> = value.split(':').collect();
+        if grid.len() == 3 {
+            let start: f32 = parse_value(name, grid[0])?;
+            let end: f32 = parse_value(name, grid[1])?;
+            let count: usize = parse_value(name, grid[2])?;
+            return match count {
+                0 => Err(format!("Invalid grid '{}' for option '--{}': count must be positive", value, name)),
+                1 => Ok(Some(vec![start])),
+                _ => Ok(Some(
+                    (0..count)
+                        .map(|i| start + (end - start) * (i as f32) / ((count - 1) as f32))
+                        .collect(),
+                )),
+            };
+        }
+
+        value
+            .split(',')
+            .map(|item| parse_value(name, item.trim()))
+            .collect::<Result<Vec<f32>, String>>()
+            .map(Some)
+    }
+}
+
+fn parse_value<T: FromStr>(name: &str, value: &str) -> Result<T, String> {
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid value '{}' for option '--{}'", value, name))
+}
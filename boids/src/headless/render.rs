@@ -0,0 +1,103 @@
+use crate::data::metrics::BoidMetric;
+use crate::data::trails::Trails;
+use crate::data::vector::Vector;
+use crate::headless::options::Options;
+use crate::headless::run::{create_world, density_from_options};
+use crate::render::canvas::Canvas;
+use crate::render::frames::FrameWriter;
+use crate::render::scene::{draw_world, Overlays, BACKGROUND_COLOR};
+use crate::render::view::View;
+
+pub const RENDER_OPTIONS: [&str; 21] = [
+    "birds",
+    "world-size",
+    "steps",
+    "dt",
+    "seed",
+    "preset",
+    "scenario",
+    "script",
+    "output",
+    "every",
+    "width",
+    "height",
+    "center",
+    "view-size",
+    "trail-length",
+    "trail-decimation",
+    "density",
+    "density-resolution",
+    "density-half-life",
+    "colour",
+    "perception",
+];
+
+/// Runs a simulation without window and renders one frame every `every` steps,
+/// the initial state included, as PNG files or as an animated GIF
+pub fn run(options: &Options) -> Result<(), String> {
+    let nb_steps: usize = options.get_or("steps", 600)?;
+    let dt: f32 = options.get_dt()?;
+    let every: usize = options.get_or::<usize>("every", 1)?.max(1);
+    let path = options.get_str("output").unwrap_or("boids.gif");
+    let metric = options.get_str("colour").map(|name| name.parse::<BoidMetric>()).transpose()?;
+    let perception: Option<usize> = options.get("perception")?;
+
+    let trail_length: usize = options.get_or("trail-length", 0)?;
+    let mut trails = if trail_length > 0 {
+        Some(Trails::new(trail_length, options.get_or("trail-decimation", 1)?))
+    } else {
+        None
+    };
+
+    let mut world = create_world(options)?;
+    let view = view_from_options(options, world.playfield_size)?;
+    let (width, height) = (view.width, view.height);
+    let mut density = if options.get_or("density", false)? {
+        Some(density_from_options(options, world.playfield_size)?)
+    } else {
+        None
+    };
+
+    let mut writer = FrameWriter::create(path, width, height, every as f32 * dt)?;
+    let mut canvas = Canvas::new(width, height, BACKGROUND_COLOR);
+    let mut nb_frames = 0;
+    for step in 0..=nb_steps {
+        if let Some(trails) = trails.as_mut() {
+            trails.record(&world);
+        }
+        if let Some(density) = density.as_mut() {
+            density.update(&world, dt);
+        }
+        if step % every == 0 {
+            let overlays = Overlays { trails: trails.as_ref(), density: density.as_ref(), metric, perception };
+            draw_world(&mut canvas, &view, &world, &overlays);
+            writer.write(&canvas)?;
+            nb_frames += 1;
+        }
+        if step < nb_steps {
+            world.compute(dt);
+        }
+    }
+    eprintln!("{} frames written to {}", nb_frames, path);
+    Ok(())
+}
+
+/// The view described by the options `width`, `height`, `center` and `view-size`
+pub fn view_from_options(options: &Options, playfield_size: f32) -> Result<View, String> {
+    let width: u32 = options.get_or("width", 800)?;
+    let height: u32 = options.get_or("height", 600)?;
+    if width == 0 || height == 0 {
+        return Err("The width and the height must be positive".to_string());
+    }
+    Ok(match (options.get_list("center")?, options.get::<f32>("view-size")?) {
+        (None, None) => View::fit(width, height, playfield_size),
+        (center, view_size) => {
+            let center = match center.as_deref() {
+                None => Vector::new(),
+                Some([x, y]) => Vector { x: *x, y: *y },
+                Some(_) => return Err("--center expects 'x,y'".to_string()),
+            };
+            View::centered(width, height, center, view_size.unwrap_or(playfield_size / 0.8))
+        }
+    })
+}
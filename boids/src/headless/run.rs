@@ -0,0 +1,165 @@
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::data::density::DensityGrid;
+use crate::data::event::report_simulation_error;
+use crate::data::metrics::FlockMetrics;
+use crate::data::preset::load_preset;
+use crate::data::scenario::load_scenario;
+use crate::data::script::load_script;
+use crate::data::world::World;
+use crate::headless::options::Options;
+use crate::network::api::ApiServer;
+use crate::network::osc::{load_osc_map, OscListener, OscMap, DEFAULT_OSC_ADDRESS};
+use crate::network::stream::StreamServer;
+use crate::render::heatmap::save_density;
+use crate::{DEFAULT_NB_BIRDS, DEFAULT_WORLD_SIZE};
+
+pub const RUN_OPTIONS: [&str; 18] = [
+    "birds",
+    "world-size",
+    "steps",
+    "dt",
+    "seed",
+    "preset",
+    "scenario",
+    "script",
+    "realtime",
+    "stream-port",
+    "stream-decimation",
+    "api-port",
+    "osc-port",
+    "osc-map",
+    "osc-bind",
+    "density-output",
+    "density-resolution",
+    "density-half-life",
+];
+
+/// The density grid described by the options `density-resolution` and `density-half-life`,
+/// covering the playfield and its margins
+pub fn density_from_options(options: &Options, playfield_size: f32) -> Result<DensityGrid, String> {
+    Ok(DensityGrid::for_playfield(
+        options.get_or("density-resolution", 64)?,
+        playfield_size,
+        options.get_or("density-half-life", 0.0)?,
+    ))
+}
+
+/// how often a paused simulation checks the remote control requests
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Creates the world described by the options `birds`, `world-size`, `preset`, `seed`,
+/// `scenario` and `script`, reporting the scenario and script errors on stderr
+pub fn create_world(options: &Options) -> Result<World, String> {
+    let mut world = World::new(
+        options.get_or("birds", DEFAULT_NB_BIRDS)?,
+        options.get_or("world-size", DEFAULT_WORLD_SIZE)?,
+    );
+    if let Some(path) = options.get_str("preset") {
+        world.parameters = load_preset(path)?;
+    }
+    if let Some(seed) = options.get::<u64>("seed")? {
+        world.seed(seed);
+    }
+    world.initialize();
+    if let Some(path) = options.get_str("scenario") {
+        world.set_scenario(load_scenario(path)?);
+    }
+    if let Some(path) = options.get_str("script") {
+        world.set_script(Some(Arc::new(load_script(path)?)));
+    }
+    world.add_observer(Box::new(report_simulation_error));
+    Ok(world)
+}
+
+/// Runs a single simulation without window, optionally streaming its state
+/// and listening to the remote controls, and prints the metrics of the final step
+pub fn run(options: &Options) -> Result<(), String> {
+    let nb_steps: usize = options.get_or("steps", 600)?;
+    let dt: f32 = options.get_dt()?;
+    let realtime: bool = options.get_or("realtime", false)?;
+
+    let mut world = create_world(options)?;
+    let density_output = options.get_str("density-output");
+    let mut density = density_output
+        .map(|_| density_from_options(options, world.playfield_size))
+        .transpose()?;
+
+    let mut stream = match options.get::<u16>("stream-port")? {
+        None => None,
+        Some(port) => {
+            let decimation = options.get_or("stream-decimation", 1)?;
+            eprintln!("streaming on ws://127.0.0.1:{}", port);
+            Some(StreamServer::start(port, decimation)?)
+        }
+    };
+
+    let api = match options.get::<u16>("api-port")? {
+        None => None,
+        Some(port) => {
+            eprintln!("remote control on http://127.0.0.1:{}", port);
+            Some(ApiServer::start(port)?)
+        }
+    };
+
+    let mut osc = match options.get::<u16>("osc-port")? {
+        None => None,
+        Some(port) => {
+            let map = match options.get_str("osc-map") {
+                None => OscMap::default_map(),
+                Some(path) => load_osc_map(path)?,
+            };
+            let address = options.get_or("osc-bind", DEFAULT_OSC_ADDRESS)?;
+            eprintln!("listening to OSC on UDP {}:{}", address, port);
+            Some(OscListener::start(address, port, map)?)
+        }
+    };
+
+    let step_duration = Duration::from_secs_f32(dt);
+    let mut paused = false;
+    let mut step = 0;
+    while nb_steps == 0 || step < nb_steps {
+        let start = Instant::now();
+        if let Some(api) = api.as_ref() {
+            api.process(&mut world, &mut paused);
+        }
+        if let Some(osc) = osc.as_mut() {
+            osc.process(&mut world);
+        }
+        if paused {
+            thread::sleep(PAUSE_POLL_INTERVAL);
+            continue;
+        }
+        world.compute(dt);
+        if let Some(stream) = stream.as_mut() {
+            stream.publish(&world);
+        }
+        if let Some(density) = density.as_mut() {
+            density.update(&world, dt);
+        }
+        if realtime {
+            if let Some(remaining) = step_duration.checked_sub(start.elapsed()) {
+                thread::sleep(remaining);
+            }
+        }
+        step += 1;
+    }
+
+    if let (Some(path), Some(density)) = (density_output, density.as_ref()) {
+        save_density(path, density)?;
+    }
+
+    let metrics = FlockMetrics::compute(&world.current);
+    println!("time,boids,polarization,nearest_neighbour_distance,milling");
+    println!(
+        "{},{},{},{},{}",
+        world.time,
+        world.current.len(),
+        metrics.polarization,
+        metrics.nearest_neighbour_distance,
+        metrics.milling
+    );
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use crate::data::event::report_simulation_error;
+use crate::data::metrics::FlockMetrics;
+use crate::data::parameters::Parameters;
+use crate::data::scenario::Scenario;
+use crate::data::script::SteeringScript;
+use crate::data::world::World;
+
+pub struct RunSettings {
+    pub nb_birds: usize,
+    pub world_size: f32,
+    pub nb_steps: usize,
+    pub dt: f32,
+    /// the metrics are averaged over this number of final steps
+    pub nb_averaging_steps: usize,
+    /// executed from the start of every run
+    pub scenario: Option<Scenario>,
+    /// custom steering rule shared by every run
+    pub script: Option<Arc<SteeringScript>>,
+}
+
+/// Runs one simulation without any rendering and returns the metrics
+/// averaged over the last `nb_averaging_steps` steps
+pub fn run_simulation(parameters: &Parameters, settings: &RunSettings, seed: u64) -> FlockMetrics {
+    let mut world = World::new(settings.nb_birds, settings.world_size);
+    world.parameters = parameters.clone();
+    world.seed(seed);
+    world.initialize();
+    if let Some(scenario) = settings.scenario.as_ref() {
+        world.set_scenario(scenario.clone());
+    }
+    world.set_script(settings.script.clone());
+    world.add_observer(Box::new(report_simulation_error));
+
+    let nb_averaging_steps = settings.nb_averaging_steps.clamp(1, settings.nb_steps.max(1));
+    let mut metrics = FlockMetrics::default();
+    for step in 0..settings.nb_steps {
+        world.compute(settings.dt);
+        if step + nb_averaging_steps >= settings.nb_steps {
+            metrics.add(&FlockMetrics::compute(&world.current));
+        }
+    }
+
+    if settings.nb_steps == 0 {
+        return FlockMetrics::compute(&world.current);
+    }
+    metrics.scale(1.0 / (nb_averaging_steps as f32));
+    metrics
+}
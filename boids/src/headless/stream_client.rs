@@ -0,0 +1,37 @@
+use tungstenite::Message;
+
+use crate::headless::options::Options;
+use crate::network::stream::decode_frame;
+
+pub const STREAM_CLIENT_OPTIONS: [&str; 2] = ["port", "frames"];
+
+/// Connects to the stream server of a running simulation and prints a summary of each frame
+pub fn run(options: &Options) -> Result<(), String> {
+    let port: u16 = options.get_or("port", 9001)?;
+    let nb_frames: usize = options.get_or("frames", 0)?;
+    let url = format!("ws://127.0.0.1:{}", port);
+    let (mut socket, _) =
+        tungstenite::connect(url.as_str()).map_err(|e| format!("Cannot connect to '{}': {}", url, e))?;
+
+    println!("step,time,world_size,boids,polarization,nearest_neighbour_distance,milling");
+    let mut nb_received = 0;
+    while nb_frames == 0 || nb_received < nb_frames {
+        let message = socket.read().map_err(|e| format!("Connection lost: {}", e))?;
+        if let Message::Binary(data) = message {
+            let frame = decode_frame(&data)?;
+            println!(
+                "{},{},{},{},{},{},{}",
+                frame.step,
+                frame.time,
+                frame.playfield_size,
+                frame.boids.len(),
+                frame.metrics.polarization,
+                frame.metrics.nearest_neighbour_distance,
+                frame.metrics.milling
+            );
+            nb_received += 1;
+        }
+    }
+    socket.close(None).ok();
+    Ok(())
+}
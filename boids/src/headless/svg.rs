@@ -0,0 +1,45 @@
+use crate::data::metrics::BoidMetric;
+use crate::headless::options::Options;
+use crate::headless::render::view_from_options;
+use crate::headless::run::create_world;
+use crate::render::svg::{save_svg, SvgOptions};
+
+pub const SVG_OPTIONS: [&str; 16] = [
+    "birds",
+    "world-size",
+    "steps",
+    "dt",
+    "seed",
+    "preset",
+    "scenario",
+    "script",
+    "output",
+    "width",
+    "height",
+    "center",
+    "view-size",
+    "arrows",
+    "boundary",
+    "colour",
+];
+
+/// Runs a simulation without window and writes its final state as an SVG image
+pub fn run(options: &Options) -> Result<(), String> {
+    let nb_steps: usize = options.get_or("steps", 600)?;
+    let dt: f32 = options.get_dt()?;
+    let path = options.get_str("output").unwrap_or("boids.svg");
+    let svg_options = SvgOptions {
+        arrows: options.get_or("arrows", false)?,
+        boundary: options.get_or("boundary", true)?,
+        metric: options.get_str("colour").map(|name| name.parse::<BoidMetric>()).transpose()?,
+    };
+
+    let mut world = create_world(options)?;
+    let view = view_from_options(options, world.playfield_size)?;
+    for _ in 0..nb_steps {
+        world.compute(dt);
+    }
+    save_svg(path, &world, &view, &svg_options)?;
+    eprintln!("{} written", path);
+    Ok(())
+}
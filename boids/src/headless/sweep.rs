@@ -0,0 +1,193 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+use crate::data::metrics::FlockMetrics;
+use crate::data::parameters::{Parameters, ParametersBuilder};
+use crate::data::scenario::load_scenario;
+use crate::data::script::load_script;
+use crate::headless::field::PARAMETER_FIELDS;
+use crate::headless::options::Options;
+use crate::headless::simulation::{run_simulation, RunSettings};
+use crate::{DEFAULT_NB_BIRDS, DEFAULT_WORLD_SIZE};
+
+/// options of the sweep command besides the swept parameter fields
+pub const SWEEP_OPTIONS: [&str; 13] = [
+    "dead-angle",
+    "safe-space-ratio",
+    "replicates",
+    "birds",
+    "world-size",
+    "steps",
+    "dt",
+    "average",
+    "seed",
+    "threads",
+    "scenario",
+    "script",
+    "output",
+];
+
+struct Job {
+    point: usize,
+    replicate: usize,
+    seed: u64,
+}
+
+/// Runs every combination of the swept parameter values `replicates` times,
+/// in parallel, and writes one CSV row of summary metrics per run
+pub fn run(options: &Options) -> Result<(), String> {
+    let points = build_points(options)?;
+    let nb_replicates: usize = options.get_or("replicates", 1)?;
+    let base_seed: u64 = options.get_or("seed", 0)?;
+    let nb_steps: usize = options.get_or("steps", 600)?;
+    let settings = RunSettings {
+        nb_birds: options.get_or("birds", DEFAULT_NB_BIRDS)?,
+        world_size: options.get_or("world-size", DEFAULT_WORLD_SIZE)?,
+        nb_steps,
+        dt: options.get_dt()?,
+        nb_averaging_steps: options.get_or("average", (nb_steps / 4).max(1))?,
+        scenario: options.get_str("scenario").map(load_scenario).transpose()?,
+        script: options.get_str("script").map(load_script).transpose()?.map(Arc::new),
+    };
+    let default_nb_threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let nb_threads: usize = options.get_or("threads", default_nb_threads)?;
+
+    let jobs: Vec<Job> = (0..points.len())
+        .flat_map(|point| (0..nb_replicates).map(move |replicate| (point, replicate)))
+        .enumerate()
+        .map(|(index, (point, replicate))| Job {
+            point,
+            replicate,
+            seed: base_seed + index as u64,
+        })
+        .collect();
+
+    let mut results = run_jobs(&jobs, &points, &settings, nb_threads.max(1));
+    results.sort_by_key(|(index, _)| *index);
+
+    let mut writer: Box<dyn Write> = match options.get_str("output") {
+        None => Box::new(std::io::stdout()),
+        Some(path) => Box::new(BufWriter::new(
+            File::create(path).map_err(|e| format!("Cannot create '{}': {}", path, e))?,
+        )),
+    };
+    write_results(&mut writer, &jobs, &points, &results)
+        .map_err(|e| format!("Cannot write the sweep results: {}", e))
+}
+
+/// Every combination of the swept values. `--dead-angle` sets the dead angle of the three
+/// rules before the per rule angles are applied, `--safe-space-ratio` sets the separation
+/// radius relative to the alignment radius of each point, whatever the order of the options.
+fn build_points(options: &Options) -> Result<Vec<Parameters>, String> {
+    let expand = |points: Vec<ParametersBuilder>, values: &[f32], apply: &dyn Fn(&mut ParametersBuilder, f32)| {
+        points
+            .iter()
+            .flat_map(|point| {
+                values.iter().map(move |value| {
+                    let mut builder = point.clone();
+                    apply(&mut builder, *value);
+                    builder
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let mut points = vec![ParametersBuilder::new()];
+    if let Some(values) = options.get_list("dead-angle")? {
+        points = expand(points, &values, &|builder, value| {
+            builder.set_dead_angle(value);
+        });
+    }
+    for field in PARAMETER_FIELDS.iter() {
+        if let Some(values) = options.get_list(field.option_name())? {
+            points = expand(points, &values, &|builder, value| field.apply(builder, value));
+        }
+    }
+    let points = points
+        .iter()
+        .map(|builder| builder.build().map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    match options.get_list("safe-space-ratio")? {
+        None => Ok(points),
+        Some(ratios) => points
+            .iter()
+            .flat_map(|point| {
+                ratios.iter().map(move |ratio| {
+                    point
+                        .to_builder()
+                        .set_separation_radius(ratio * point.alignment().radius())
+                        .build()
+                        .map_err(|e| e.to_string())
+                })
+            })
+            .collect(),
+    }
+}
+
+fn run_jobs(
+    jobs: &[Job],
+    points: &[Parameters],
+    settings: &RunSettings,
+    nb_threads: usize,
+) -> Vec<(usize, FlockMetrics)> {
+    let next_job = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..nb_threads.min(jobs.len()) {
+            let sender = sender.clone();
+            let next_job = &next_job;
+            scope.spawn(move || loop {
+                let index = next_job.fetch_add(1, Ordering::Relaxed);
+                if index >= jobs.len() {
+                    break;
+                }
+                let job = &jobs[index];
+                let metrics = run_simulation(&points[job.point], settings, job.seed);
+                eprintln!(
+                    "run {}/{} done (point {}, replicate {})",
+                    index + 1,
+                    jobs.len(),
+                    job.point,
+                    job.replicate
+                );
+                sender.send((index, metrics)).unwrap();
+            });
+        }
+    });
+    drop(sender);
+
+    receiver.iter().collect()
+}
+
+fn write_results(
+    writer: &mut impl Write,
+    jobs: &[Job],
+    points: &[Parameters],
+    results: &[(usize, FlockMetrics)],
+) -> std::io::Result<()> {
+    write!(writer, "point,replicate,seed")?;
+    for field in PARAMETER_FIELDS.iter() {
+        write!(writer, ",{}", field.column_name())?;
+    }
+    writeln!(writer, ",polarization,nearest_neighbour_distance,milling")?;
+
+    for (index, metrics) in results {
+        let job = &jobs[*index];
+        let parameters = &points[job.point];
+        write!(writer, "{},{},{}", job.point, job.replicate, job.seed)?;
+        for field in PARAMETER_FIELDS.iter() {
+            write!(writer, ",{}", field.get(parameters))?;
+        }
+        writeln!(
+            writer,
+            ",{},{},{}",
+            metrics.polarization, metrics.nearest_neighbour_distance, metrics.milling
+        )?;
+    }
+    writer.flush()
+}
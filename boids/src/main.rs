@@ -1,10 +1,35 @@
 use raylib::prelude::*;
 use raylib::consts::*;
 
-use crate::data::boid::Boid;
+use crate::camera::{CameraController, CameraMode};
+use crate::clock::SimulationClock;
+use crate::draw::{
+    draw_birds, draw_density, draw_inspector, draw_legend, draw_obstacles_and_predators, draw_perception,
+    draw_selection, draw_trails,
+};
+use crate::data::parameters::Parameters;
+use crate::data::density::DensityGrid;
+use crate::data::event::report_simulation_error;
+use crate::data::metrics::BoidMetric;
+use crate::data::preset::load_preset;
+use crate::data::trails::Trails;
+use crate::data::vector::Vector;
+use crate::data::scenario::load_scenario;
+use crate::data::script::load_script;
 use crate::data::world::World;
+use crate::headless::options::Options;
+use crate::network::RemoteChanges;
+use crate::network::api::ApiServer;
+use crate::network::osc::{load_osc_map, OscListener, OscMap, DEFAULT_OSC_ADDRESS};
+use crate::network::stream::StreamServer;
+use crate::render::canvas::Rgb;
+use crate::render::colour::boid_colours;
+use crate::render::heatmap::save_density;
+use crate::render::svg::{save_svg, SvgOptions};
+use crate::render::view::View;
 use gui::gui::{Gui, GuiData};
-use gui::widget::Widget::{VBox, Label, Slider};
+use gui::widget::Widget::{HBox, VBox, Label, Slider};
+use gui::hbox::HBoxPar;
 use gui::vbox::VBoxPar;
 use gui::padding::Padding;
 use gui::border::Border;
@@ -19,53 +44,74 @@ use gui::alignment::VAlignment::Top;
 use gui::alignment::HAlignment::Left;
 use gui::position::Coordinate::Absolute;
 use gui::slider::SliderPar;
-use std::process::{abort, exit};
-use gui::event::Event::Drag;
+use std::process::exit;
+use gui::event::Event::{Click, Drag};
 use gui::label::LabelPar;
 use raylib::ease::Tween;
 use std::fs::File;
 use std::path::Path;
+use std::sync::Arc;
 
+mod camera;
+mod clock;
 mod data;
+mod draw;
+mod headless;
+mod network;
+mod render;
 
-const DEFAULT_NB_BIRDS: usize = 2000;
-const DEFAULT_WORLD_SIZE: f32 = 10.;
+pub const DEFAULT_NB_BIRDS: usize = 2000;
+pub const DEFAULT_WORLD_SIZE: f32 = 10.;
 
 pub struct ScreenSize {
     pub width: i32,
     pub height: i32,
 }
 
+const APP_OPTIONS: [&str; 13] = [
+    "preset",
+    "scenario",
+    "script",
+    "stream-port",
+    "stream-decimation",
+    "api-port",
+    "osc-port",
+    "osc-map",
+    "osc-bind",
+    "trail-length",
+    "trail-decimation",
+    "density-resolution",
+    "density-half-life",
+];
+
 const COHESION_ID: &str = "cohesion_id";
 const SEPARATION_ID: &str = "separation_id";
-const DEAD_ANGLE_ID: &str = "dead_angle_id";
-const SAFE_SPACE_RATIO_ID: &str = "safe_space_ratio_id";
 const ALIGNMENT_ID: &str = "alignment_id";
-
-fn draw_birds(d: &mut impl RaylibDraw, boids: &[Boid], bird_size: f32) {
-    {
-        let size_factor: f32 = 1.2;
-
-        let mut head = Vector2::zero();
-        let mut left_wing = Vector2::zero();
-        let mut right_wing = Vector2::zero();
-
-        for boid in boids {
-            let nvx = size_factor * bird_size * boid.velocity.x / boid.speed();
-            let nvy = size_factor * bird_size * boid.velocity.y / boid.speed();
-            head.x = nvx + boid.position.x;
-            head.y = nvy + boid.position.y;
-
-            left_wing.x = nvy * 0.3 + boid.position.x;
-            left_wing.y = -nvx * 0.3 + boid.position.y;
-
-            right_wing.x = -nvy * 0.3 + boid.position.x;
-            right_wing.y = nvx * 0.3 + boid.position.y;
-
-            d.draw_triangle(head, left_wing, right_wing, Color::BLACK);
-        }
-    }
-}
+const SEPARATION_RADIUS_ID: &str = "separation_radius_id";
+const ALIGNMENT_RADIUS_ID: &str = "alignment_radius_id";
+const COHESION_RADIUS_ID: &str = "cohesion_radius_id";
+const SEPARATION_DEAD_ANGLE_ID: &str = "separation_dead_angle_id";
+const ALIGNMENT_DEAD_ANGLE_ID: &str = "alignment_dead_angle_id";
+const COHESION_DEAD_ANGLE_ID: &str = "cohesion_dead_angle_id";
+const SEPARATION_KERNEL_ID: &str = "separation_kernel_id";
+const ALIGNMENT_KERNEL_ID: &str = "alignment_kernel_id";
+const COHESION_KERNEL_ID: &str = "cohesion_kernel_id";
+const LONG_RANGE_ID: &str = "long_range_id";
+const TRAILS_ID: &str = "trails_id";
+const DENSITY_ID: &str = "density_id";
+const COLOUR_ID: &str = "colour_id";
+const PERCEPTION_ID: &str = "perception_id";
+const PAUSE_ID: &str = "pause_id";
+const STEP_ID: &str = "step_id";
+const SLOWER_ID: &str = "slower_id";
+const FASTER_ID: &str = "faster_id";
+
+/// a click selects the closest boid if it is at most this number of pixels away
+const PICK_DISTANCE: f32 = 12.0;
+/// the colours of a moving flock are computed again after this number of steps
+const COLOUR_REFRESH_STEPS: usize = 10;
+/// zoom factor of one notch of the mouse wheel
+const WHEEL_ZOOM_FACTOR: f32 = 1.2;
 
 pub struct BoidsModel {
     pub gui_width: f32,
@@ -99,10 +145,177 @@ impl BoidsModel {
     }
 }
 
+/// value shown by the slider matching `action_id`, the factors and radii are shown x100
+fn slider_value(parameters: &Parameters, action_id: &str) -> f32 {
+    match action_id {
+        ALIGNMENT_ID => 100.0 * parameters.alignment_factor(),
+        ALIGNMENT_RADIUS_ID => 100.0 * parameters.alignment().radius(),
+        ALIGNMENT_DEAD_ANGLE_ID => parameters.alignment().dead_angle(),
+        COHESION_ID => 100.0 * parameters.cohesion_factor(),
+        COHESION_RADIUS_ID => 100.0 * parameters.cohesion().radius(),
+        COHESION_DEAD_ANGLE_ID => parameters.cohesion().dead_angle(),
+        SEPARATION_ID => 100.0 * parameters.separation_factor(),
+        SEPARATION_RADIUS_ID => 100.0 * parameters.separation().radius(),
+        SEPARATION_DEAD_ANGLE_ID => parameters.separation().dead_angle(),
+        LONG_RANGE_ID => 100.0 * parameters.long_range_factor(),
+        _ => 0.0,
+    }
+}
+
+/// text of the clickable label showing the kernel of the rule matching `action_id`
+fn kernel_text(parameters: &Parameters, action_id: &str) -> String {
+    let (rule, kernel) = match action_id {
+        SEPARATION_KERNEL_ID => ("Separation", parameters.separation().kernel()),
+        ALIGNMENT_KERNEL_ID => ("Alignment", parameters.alignment().kernel()),
+        _ => ("Cohesion", parameters.cohesion().kernel()),
+    };
+    format!("{} kernel: {}", rule, kernel)
+}
+
+/// the part of the world shown by `camera` in a screen of `screen_size`
+fn camera_view(camera: &Camera2D, screen_size: &ScreenSize) -> View {
+    View {
+        width: screen_size.width as u32,
+        height: screen_size.height as u32,
+        center: Vector {
+            x: camera.target.x + (screen_size.width as f32 * 0.5 - camera.offset.x) / camera.zoom,
+            y: camera.target.y + (screen_size.height as f32 * 0.5 - camera.offset.y) / camera.zoom,
+        },
+        zoom: camera.zoom,
+    }
+}
+
+/// first `boids_<n>.<extension>` not yet existing in the current directory
+fn next_export_path(extension: &str) -> String {
+    (1..)
+        .map(|n| format!("boids_{:03}.{}", n, extension))
+        .find(|path| !Path::new(path).exists())
+        .unwrap()
+}
+
+fn colour_text(metric: Option<BoidMetric>) -> String {
+    format!("Colour: {}", metric.map(|metric| metric.name()).unwrap_or("none"))
+}
+
+fn clock_text(time: f32, time_scale: f32) -> String {
+    format!("Time: {:.1} s ({}x)", time, time_scale)
+}
+
+fn toggle_text(name: &str, visible: bool) -> String {
+    format!("{}: {}", name, if visible { "on" } else { "off" })
+}
+
+fn report_error(result: Result<(), impl std::fmt::Display>) {
+    if let Err(error) = result {
+        eprintln!("{}", error);
+    }
+}
+
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() && !args[0].starts_with("--") {
+        if let Err(message) = headless::run(&args) {
+            eprintln!("{}", message);
+            exit(1);
+        }
+        return;
+    }
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", headless::USAGE);
+        return;
+    }
+
+    let app_options = Options::parse(&args, &APP_OPTIONS).unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        exit(1)
+    });
+
     let mut app_state = BoidsModel::new(DEFAULT_NB_BIRDS, DEFAULT_WORLD_SIZE);
+    if let Some(path) = app_options.get_str("preset") {
+        app_state.world.parameters = load_preset(path).unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            exit(1)
+        });
+    }
 
     app_state.world.initialize();
+    if let Some(path) = app_options.get_str("scenario") {
+        let scenario = load_scenario(path).unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            exit(1)
+        });
+        app_state.world.set_scenario(scenario);
+    }
+    if let Some(path) = app_options.get_str("script") {
+        let script = load_script(path).unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            exit(1)
+        });
+        app_state.world.set_script(Some(Arc::new(script)));
+    }
+    app_state.world.add_observer(Box::new(report_simulation_error));
+
+    let mut stream = app_options
+        .get::<u16>("stream-port")
+        .and_then(|port| {
+            let decimation = app_options.get_or("stream-decimation", 1)?;
+            port.map(|port| StreamServer::start(port, decimation)).transpose()
+        })
+        .unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            exit(1)
+        });
+
+    let api = app_options
+        .get::<u16>("api-port")
+        .and_then(|port| port.map(ApiServer::start).transpose())
+        .unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            exit(1)
+        });
+    let mut osc = app_options
+        .get::<u16>("osc-port")
+        .and_then(|port| {
+            let map = match app_options.get_str("osc-map") {
+                None => OscMap::default_map(),
+                Some(path) => load_osc_map(path)?,
+            };
+            let address = app_options.get_or("osc-bind", DEFAULT_OSC_ADDRESS)?;
+            port.map(|port| OscListener::start(address, port, map)).transpose()
+        })
+        .unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            exit(1)
+        });
+    let mut clock = SimulationClock::new();
+
+    let mut trails = app_options
+        .get_or("trail-length", 30)
+        .and_then(|length| Ok(Trails::new(length, app_options.get_or("trail-decimation", 2)?)))
+        .unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            exit(1)
+        });
+    let mut trails_visible = false;
+
+    let mut density = app_options
+        .get_or("density-resolution", 64)
+        .and_then(|resolution| {
+            let half_life = app_options.get_or("density-half-life", 10.0)?;
+            Ok(DensityGrid::for_playfield(resolution, app_state.world.playfield_size, half_life))
+        })
+        .unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            exit(1)
+        });
+    let mut density_visible = false;
+    let mut colour_metric: Option<BoidMetric> = None;
+    let mut colours: Vec<Rgb> = Vec::new();
+    let mut colour_range: Option<(f32, f32)> = None;
+    let mut colours_stale = true;
+    let mut steps_since_colours = 0;
+    let mut perception_visible = false;
+    let mut selected_boid: Option<usize> = None;
 
 
     let (mut rl, thread) = raylib::init()
@@ -129,6 +342,7 @@ fn main() {
     gui.add_border("default", Line { color: Color::BLACK, thickness: 1.0 });
     gui.add_background("red", Solid { idle_color: red, hoovered_color: red, armed_color: red });
     gui.add_text_style("default", "default", Color::BLACK, 0.0);
+    gui.add_text_style("small", "small", Color::BLACK, 0.0);
 
     let container = {
         let par = VBoxPar::new();
@@ -144,118 +358,137 @@ fn main() {
     };
 
 
-    {
-        let par = LabelPar::new();
-        par.set_text(&gui, "Alignment")
-            .set_text_style("default")
-            .set_border_style("none")
-            .enable_fill_width(&gui, Enabled { weight: 1 });
-        gui.add_child(container, Label(par));
-        let par = SliderPar::new();
-        par.set_value(&gui, 100.0 * app_state.world.parameters.alignment_factor)
-            .set_value_min(&gui, 0.0)
-            .set_value_max(&gui, 100.0)
-            .set_text_style("default")
-            .set_action_id(ALIGNMENT_ID)
-            .set_text_style("default")
-            .enable_fill_width(&gui, Enabled { weight: 1 });
-
-        gui.add_child(container, Slider(par));
-    }
-
-    {
+    let sliders = [
+        ("Alignment", ALIGNMENT_ID, 0.0, 100.0),
+        ("Alignment radius", ALIGNMENT_RADIUS_ID, 5.0, 200.0),
+        ("Alignment dead angle", ALIGNMENT_DEAD_ANGLE_ID, 0.0, 180.0),
+        ("Cohesion", COHESION_ID, 0.0, 100.0),
+        ("Cohesion radius", COHESION_RADIUS_ID, 5.0, 200.0),
+        ("Cohesion dead angle", COHESION_DEAD_ANGLE_ID, 0.0, 180.0),
+        ("Separation", SEPARATION_ID, 0.0, 100.0),
+        ("Separation radius", SEPARATION_RADIUS_ID, 5.0, 200.0),
+        ("Separation dead angle", SEPARATION_DEAD_ANGLE_ID, 0.0, 180.0),
+        ("Long range", LONG_RANGE_ID, 0.0, 100.0),
+    ];
+
+    let mut slider_widgets = Vec::with_capacity(sliders.len());
+    for (index, (text, action_id, value_min, value_max)) in sliders.iter().enumerate() {
+        let top_padding = if index > 0 && index % 3 == 0 { 20.0 } else { 0.0 };
         let par = LabelPar::new();
-        par.set_text(&gui, "Cohesion")
-            .set_text_style("default")
+        par.set_text(&gui, text)
+            .set_text_style("small")
             .set_border_style("none")
-            .set_padding(&gui, Padding::new(40.0, 0.0, 0.0, 0.0))
+            .set_padding(&gui, Padding::new(top_padding, 0.0, 0.0, 0.0))
             .enable_fill_width(&gui, Enabled { weight: 1 });
         gui.add_child(container, Label(par));
         let par = SliderPar::new();
-        par.set_value(&gui, 100.0 * app_state.world.parameters.cohesion_factor)
-            .set_value_min(&gui, 0.0)
-            .set_value_max(&gui, 100.0)
-            .set_text_style("default")
-            .set_action_id(COHESION_ID)
-            .set_text_style("default")
+        par.set_value(&gui, slider_value(&app_state.world.parameters, action_id))
+            .set_value_min(&gui, *value_min)
+            .set_value_max(&gui, *value_max)
+            .set_text_style("small")
+            .set_action_id(action_id)
             .enable_fill_width(&gui, Enabled { weight: 1 });
 
-        gui.add_child(container, Slider(par));
+        slider_widgets.push((*action_id, gui.add_child(container, Slider(par))));
     }
 
-    {
+    let kernel_labels: Vec<_> = [SEPARATION_KERNEL_ID, ALIGNMENT_KERNEL_ID, COHESION_KERNEL_ID]
+        .iter()
+        .map(|action_id| {
+            let par = LabelPar::new();
+            par.set_text(&gui, &kernel_text(&app_state.world.parameters, action_id))
+                .set_text_style("small")
+                .set_action_id(action_id)
+                .set_clickable(true)
+                .set_padding(&gui, Padding::new(if *action_id == SEPARATION_KERNEL_ID { 20.0 } else { 0.0 }, 0.0, 0.0, 0.0))
+                .enable_fill_width(&gui, Enabled { weight: 1 });
+            (*action_id, gui.add_child(container, Label(par)))
+        })
+        .collect();
+
+    let toggle_labels: Vec<_> = [(TRAILS_ID, "Trails"), (DENSITY_ID, "Density"), (PERCEPTION_ID, "Perception")]
+        .iter()
+        .map(|(action_id, name)| {
+            let par = LabelPar::new();
+            par.set_text(&gui, &toggle_text(name, false))
+                .set_text_style("small")
+                .set_action_id(action_id)
+                .set_clickable(true)
+                .set_padding(&gui, Padding::new(if *action_id == TRAILS_ID { 20.0 } else { 0.0 }, 0.0, 0.0, 0.0))
+                .enable_fill_width(&gui, Enabled { weight: 1 });
+            (*action_id, *name, gui.add_child(container, Label(par)))
+        })
+        .collect();
+
+    let colour_label = {
         let par = LabelPar::new();
-        par.set_text(&gui, "Separation")
-            .set_text_style("default")
-            .set_border_style("none")
-            .set_padding(&gui, Padding::new(40.0, 0.0, 0.0, 0.0))
-            .enable_fill_width(&gui, Enabled { weight: 1 });
-        gui.add_child(container, Label(par));
-        let par = SliderPar::new();
-        par.set_value(&gui, 100.0 * app_state.world.parameters.separation_factor)
-            .set_value_min(&gui, 0.0)
-            .set_value_max(&gui, 100.0)
-            .set_text_style("default")
-            .set_action_id(SEPARATION_ID)
-            .set_text_style("default")
+        par.set_text(&gui, &colour_text(colour_metric))
+            .set_text_style("small")
+            .set_action_id(COLOUR_ID)
+            .set_clickable(true)
             .enable_fill_width(&gui, Enabled { weight: 1 });
+        gui.add_child(container, Label(par))
+    };
 
-        gui.add_child(container, Slider(par));
-    }
 
-    {
+    let clock_label = {
         let par = LabelPar::new();
-        par.set_text(&gui, "Dead Angle")
-            .set_text_style("default")
+        par.set_text(&gui, &clock_text(0.0, clock.time_scale()))
+            .set_text_style("small")
             .set_border_style("none")
-            .set_padding(&gui, Padding::new(40.0, 0.0, 0.0, 0.0))
-            .enable_fill_width(&gui, Enabled { weight: 1 });
-        gui.add_child(container, Label(par));
-        let par = SliderPar::new();
-        par.set_value(&gui, app_state.world.parameters.dead_angle())
-            .set_value_min(&gui, 0.0)
-            .set_value_max(&gui, 180.0)
-            .set_text_style("default")
-            .set_action_id(DEAD_ANGLE_ID)
-            .set_text_style("default")
+            .set_padding(&gui, Padding::new(20.0, 0.0, 0.0, 0.0))
             .enable_fill_width(&gui, Enabled { weight: 1 });
+        gui.add_child(container, Label(par))
+    };
 
-        gui.add_child(container, Slider(par));
-    }
-
-    {
-        let par = LabelPar::new();
-        par.set_text(&gui, "Safe Space Ratio")
-            .set_text_style("default")
+    let controls = {
+        let par = HBoxPar::new();
+        par.set_spacing(&gui, 5.0)
             .set_border_style("none")
-            .set_padding(&gui, Padding::new(40.0, 0.0, 0.0, 0.0))
             .enable_fill_width(&gui, Enabled { weight: 1 });
-        gui.add_child(container, Label(par));
-        let par = SliderPar::new();
-        par.set_value(&gui, app_state.world.parameters.safe_space_ratio * 100.0)
-            .set_value_min(&gui, 0.0)
-            .set_value_max(&gui, 100.0)
-            .set_text_style("default")
-            .set_action_id(SAFE_SPACE_RATIO_ID)
-            .set_text_style("default")
-            .enable_fill_width(&gui, Enabled { weight: 1 });
-
-        gui.add_child(container, Slider(par));
-    }
-
-
-    let mut camera = Camera2D {
-        target: Vector2 { x: 0., y: 0. },
-        offset: Vector2 { x: 0.0, y: 0.0 },
-        rotation: 0.0,
-        zoom: 1.0,
+        gui.add_child(container, HBox(par))
+    };
+    let control_labels: Vec<_> = [(PAUSE_ID, "Pause"), (STEP_ID, "Step"), (SLOWER_ID, "Slower"), (FASTER_ID, "Faster")]
+        .iter()
+        .map(|(action_id, text)| {
+            let par = LabelPar::new();
+            par.set_text(&gui, text)
+                .set_text_style("small")
+                .set_action_id(action_id)
+                .set_clickable(true);
+            gui.add_child(controls, Label(par))
+        })
+        .collect();
+    let pause_label = control_labels[0];
+
+
+    // shows the parameters changed by the remote controls
+    let refresh_parameter_widgets = |gui: &Gui, parameters: &Parameters| {
+        for (action_id, index) in slider_widgets.iter() {
+            if let Some(Slider(par)) = gui.get_widget(*index) {
+                par.set_value(gui, slider_value(parameters, action_id));
+            }
+        }
+        for (action_id, index) in kernel_labels.iter() {
+            if let Some(Label(par)) = gui.get_widget(*index) {
+                par.set_text(gui, &kernel_text(parameters, action_id));
+            }
+        }
     };
 
 
     app_state.screen_size.width = rl.get_screen_width();
     app_state.screen_size.height = rl.get_screen_height();
 
+    let mut camera_controller = CameraController::new(app_state.camera_offset(), app_state.camera_zoom());
+    let mut camera = camera_controller.camera();
+    // the right button was pressed over the world and is dragging it
+    let mut panning = false;
+    let mut last_mouse_position = Vector2::zero();
+
     let mut mouse_state = MouseState::new();
+    // the same mouse, in world coordinates
+    let mut world_mouse = MouseState::new();
     let mut screen_size: Size = Size::new(rl.get_screen_width() as f32, rl.get_screen_height() as f32);
     let mut should_quit = false;
 
@@ -270,6 +503,7 @@ fn main() {
         let mut d = rl.begin_drawing(&thread);
         let dt = d.get_frame_time();
         mouse_state.update(&d);
+        world_mouse.update_2d(&d, camera);
 
 
         if let Some(t) = tween.as_mut() {
@@ -286,44 +520,293 @@ fn main() {
             gui_visible = !gui_visible
         }
 
+        if d.is_key_released(KeyboardKey::KEY_O) {
+            let occlusion = !app_state.world.parameters.occlusion();
+            report_error(app_state.world.update_parameters(|b| { b.set_occlusion(occlusion); }));
+        }
+
+        if d.is_key_released(KeyboardKey::KEY_C) {
+            let collisions = !app_state.world.parameters.collisions();
+            report_error(app_state.world.update_parameters(|b| { b.set_collisions(collisions); }));
+        }
+
+        if d.is_key_released(KeyboardKey::KEY_S) {
+            let path = next_export_path("svg");
+            let options = SvgOptions { boundary: true, arrows: false, metric: colour_metric };
+            let view = camera_view(&camera, &app_state.screen_size);
+            match save_svg(&path, &app_state.world, &view, &options) {
+                Ok(()) => println!("{} written", path),
+                Err(error) => eprintln!("{}", error),
+            }
+        }
+
+        let mut next_colour = d.is_key_released(KeyboardKey::KEY_M);
+        let mut toggled = None;
+        let mut toggle_pause = d.is_key_released(KeyboardKey::KEY_SPACE);
+        let mut single_step = d.is_key_released(KeyboardKey::KEY_RIGHT);
+        let mut time_scale_change: i32 = 0;
+        if d.is_key_released(KeyboardKey::KEY_EQUAL) || d.is_key_released(KeyboardKey::KEY_KP_ADD) {
+            time_scale_change += 1;
+        }
+        if d.is_key_released(KeyboardKey::KEY_MINUS) || d.is_key_released(KeyboardKey::KEY_KP_SUBTRACT) {
+            time_scale_change -= 1;
+        }
+        if d.is_key_released(KeyboardKey::KEY_P) {
+            toggled = Some(PERCEPTION_ID);
+        }
+
+        if d.is_key_released(KeyboardKey::KEY_H) {
+            for path in [next_export_path("csv"), next_export_path("png")] {
+                match save_density(&path, &density) {
+                    Ok(()) => println!("{} written", path),
+                    Err(error) => eprintln!("{}", error),
+                }
+            }
+        }
+
+        let mouse_position = *mouse_state.mouse_position();
+        let over_world = mouse_position.x >= offset.x + root.widget_width();
+        if world_mouse.left().is_pressed() && over_world {
+            let position = world_mouse.mouse_position();
+            selected_boid = app_state
+                .world
+                .current
+                .nearest(&Vector { x: position.x, y: position.y })
+                .filter(|(_, distance)| distance * camera.zoom <= PICK_DISTANCE)
+                .map(|(index, _)| index);
+        }
+        // the boid may have been removed by the remote controls
+        if selected_boid.is_some_and(|index| index >= app_state.world.current.len()) {
+            selected_boid = None;
+        }
+
         if d.is_window_resized() {
             screen_size = Size::new(d.get_screen_width() as f32, d.get_screen_height() as f32);
             app_state.screen_size.width = d.get_screen_width();
             app_state.screen_size.height = d.get_screen_height();
-            camera.offset = app_state.camera_offset();
-            camera.target.x = 0.0;
-            camera.target.y = 0.0;
-            camera.zoom = app_state.camera_zoom();
         }
 
+        let fit_zoom = app_state.camera_zoom();
+        let wheel = d.get_mouse_wheel_move();
+        if wheel != 0.0 && over_world {
+            camera_controller.zoom_at(mouse_position, WHEEL_ZOOM_FACTOR.powf(wheel), fit_zoom);
+        }
+        if mouse_state.right().is_pressed() {
+            panning = over_world;
+        }
+        if panning && mouse_state.right().is_down() {
+            camera_controller.pan(Vector2 {
+                x: mouse_position.x - last_mouse_position.x,
+                y: mouse_position.y - last_mouse_position.y,
+            });
+        } else {
+            panning = false;
+        }
+        last_mouse_position = mouse_position;
+
+        let toggle_mode = |current: CameraMode, mode: CameraMode| if current == mode { CameraMode::Free } else { mode };
+        if d.is_key_released(KeyboardKey::KEY_V) {
+            camera_controller.set_mode(CameraMode::Fit);
+        }
+        if d.is_key_released(KeyboardKey::KEY_G) {
+            camera_controller.set_mode(toggle_mode(camera_controller.mode(), CameraMode::Centroid));
+        }
+        if d.is_key_released(KeyboardKey::KEY_F) && selected_boid.is_some() {
+            camera_controller.set_mode(toggle_mode(camera_controller.mode(), CameraMode::Selected));
+        }
+        let followed = match camera_controller.mode() {
+            CameraMode::Centroid => app_state.world.current.centroid(),
+            CameraMode::Selected => selected_boid.map(|index| app_state.world.current.position(index)),
+            _ => None,
+        };
+        camera_controller.update(
+            dt,
+            app_state.camera_offset(),
+            fit_zoom,
+            followed.map(|point| Vector2 { x: point.x, y: point.y }),
+        );
+        camera = camera_controller.camera();
+
         d.clear_background(Color::WHITE);
         d.draw_fps(app_state.screen_size.width - 100, 0);
 
         gui.layout_and_render(&mut d, &screen_size, &mouse_state, &offset);
 
+        // the colours are computed again when the flock, the parameters or the metric changed,
+        // and every few steps only while the flock moves
+        if colours_stale || colours.len() != app_state.world.current.len() {
+            let (new_colours, range) = boid_colours(&app_state.world, colour_metric);
+            colours = new_colours;
+            colour_range = range;
+            colours_stale = false;
+            steps_since_colours = 0;
+        }
+
         {
             let mut d = d.begin_mode2D(camera);
+            if density_visible {
+                draw_density(&mut d, &density);
+            }
+            draw_obstacles_and_predators(&mut d, &app_state.world);
+            if trails_visible {
+                draw_trails(&mut d, &trails, app_state.world.parameters.bird_size());
+            }
             draw_birds(
                 &mut d,
-                &(app_state.world.current[..]),
-                app_state.world.parameters.bird_size,
+                &app_state.world.current,
+                app_state.world.parameters.bird_size(),
+                &colours,
             );
+            if let Some(index) = selected_boid {
+                if perception_visible {
+                    draw_perception(&mut d, &app_state.world, index);
+                }
+                let bird_size = app_state.world.parameters.bird_size();
+                draw_selection(&mut d, &app_state.world.current.boid(index), bird_size);
+            }
+        }
+        if let Some(index) = selected_boid {
+            let following = camera_controller.mode() == CameraMode::Selected;
+            draw_inspector(&mut d, &app_state.world, index, following, &app_state.screen_size);
+        }
+        if let (Some(metric), Some(range)) = (colour_metric, colour_range) {
+            draw_legend(&mut d, metric, range, &app_state.screen_size);
         }
 
         let events = gui.get_events();
         for event in events.iter() {
+            if let Click(p) = event {
+                let action_id = p.action_id();
+                let result = match action_id {
+                    SEPARATION_KERNEL_ID => {
+                        let kernel = app_state.world.parameters.separation().kernel().next();
+                        app_state.world.update_parameters(|b| { b.set_separation_kernel(kernel); })
+                    }
+                    ALIGNMENT_KERNEL_ID => {
+                        let kernel = app_state.world.parameters.alignment().kernel().next();
+                        app_state.world.update_parameters(|b| { b.set_alignment_kernel(kernel); })
+                    }
+                    COHESION_KERNEL_ID => {
+                        let kernel = app_state.world.parameters.cohesion().kernel().next();
+                        app_state.world.update_parameters(|b| { b.set_cohesion_kernel(kernel); })
+                    }
+                    TRAILS_ID | DENSITY_ID | PERCEPTION_ID => {
+                        toggled = Some(action_id);
+                        Ok(())
+                    }
+                    COLOUR_ID => {
+                        next_colour = true;
+                        Ok(())
+                    }
+                    PAUSE_ID => {
+                        toggle_pause = true;
+                        Ok(())
+                    }
+                    STEP_ID => {
+                        single_step = true;
+                        Ok(())
+                    }
+                    SLOWER_ID => {
+                        time_scale_change -= 1;
+                        Ok(())
+                    }
+                    FASTER_ID => {
+                        time_scale_change += 1;
+                        Ok(())
+                    }
+                    &_ => Ok(())
+                };
+                report_error(result);
+                for (label_action_id, index) in kernel_labels.iter() {
+                    if *label_action_id == action_id {
+                        if let Some(Label(par)) = gui.get_widget(*index) {
+                            par.set_text(&gui, &kernel_text(&app_state.world.parameters, action_id));
+                        }
+                    }
+                }
+            }
             if let Drag(p) = event {
-                match p.action_id() {
-                    COHESION_ID => { app_state.world.parameters.cohesion_factor = p.value() / 100. }
-                    ALIGNMENT_ID => { app_state.world.parameters.alignment_factor = p.value() / 100. }
-                    SEPARATION_ID => { app_state.world.parameters.separation_factor = p.value() / 100. }
-                    DEAD_ANGLE_ID => { app_state.world.parameters.set_dead_angle(p.value()) }
-                    SAFE_SPACE_RATIO_ID => { app_state.world.parameters.safe_space_ratio = p.value() * 0.01 }
-                    &_ => {}
+                let value = p.value();
+                let result = match p.action_id() {
+                    COHESION_ID => app_state.world.update_parameters(|b| { b.set_cohesion_factor(value / 100.); }),
+                    ALIGNMENT_ID => app_state.world.update_parameters(|b| { b.set_alignment_factor(value / 100.); }),
+                    SEPARATION_ID => app_state.world.update_parameters(|b| { b.set_separation_factor(value / 100.); }),
+                    SEPARATION_RADIUS_ID => app_state.world.update_parameters(|b| { b.set_separation_radius(value / 100.); }),
+                    ALIGNMENT_RADIUS_ID => app_state.world.update_parameters(|b| { b.set_alignment_radius(value / 100.); }),
+                    COHESION_RADIUS_ID => app_state.world.update_parameters(|b| { b.set_cohesion_radius(value / 100.); }),
+                    SEPARATION_DEAD_ANGLE_ID => app_state.world.update_parameters(|b| { b.set_separation_dead_angle(value); }),
+                    ALIGNMENT_DEAD_ANGLE_ID => app_state.world.update_parameters(|b| { b.set_alignment_dead_angle(value); }),
+                    COHESION_DEAD_ANGLE_ID => app_state.world.update_parameters(|b| { b.set_cohesion_dead_angle(value); }),
+                    LONG_RANGE_ID => app_state.world.update_parameters(|b| { b.set_long_range_factor(value / 100.); }),
+                    &_ => Ok(())
+                };
+                report_error(result);
+                colours_stale = true;
+            }
+        }
+
+        if let Some(action_id) = toggled {
+            let visible = match action_id {
+                TRAILS_ID => {
+                    trails.clear();
+                    &mut trails_visible
                 }
+                DENSITY_ID => &mut density_visible,
+                _ => &mut perception_visible,
+            };
+            *visible = !*visible;
+            for (label_action_id, name, index) in toggle_labels.iter() {
+                if *label_action_id == action_id {
+                    if let Some(Label(par)) = gui.get_widget(*index) {
+                        par.set_text(&gui, &toggle_text(name, *visible));
+                    }
+                }
+            }
+        }
+
+        if next_colour {
+            colour_metric = BoidMetric::next(colour_metric);
+            colours_stale = true;
+            if let Some(Label(par)) = gui.get_widget(colour_label) {
+                par.set_text(&gui, &colour_text(colour_metric));
+            }
+        }
+
+        let mut changes = RemoteChanges::default();
+        if let Some(api) = api.as_ref() {
+            changes.merge(api.process(&mut app_state.world, &mut clock.paused));
+        }
+        if let Some(osc) = osc.as_mut() {
+            changes.merge(osc.process(&mut app_state.world));
+        }
+        if changes.parameters {
+            refresh_parameter_widgets(&gui, &app_state.world.parameters);
+        }
+        colours_stale |= changes.parameters || changes.flock;
+
+        if toggle_pause {
+            clock.toggle_pause();
+        }
+        clock.change_time_scale(time_scale_change);
+        let (nb_steps, step_duration) = clock.frame_steps(dt, single_step);
+        steps_since_colours += nb_steps;
+        colours_stale |= steps_since_colours >= COLOUR_REFRESH_STEPS;
+        for _ in 0..nb_steps {
+            app_state.world.compute(step_duration);
+            if let Some(stream) = stream.as_mut() {
+                stream.publish(&app_state.world);
+            }
+            if trails_visible {
+                trails.record(&app_state.world);
             }
+            density.update(&app_state.world, step_duration);
         }
 
-        app_state.world.compute(dt);
+        if let Some(Label(par)) = gui.get_widget(pause_label) {
+            par.set_text(&gui, if clock.paused { "Resume" } else { "Pause" });
+        }
+        if let Some(Label(par)) = gui.get_widget(clock_label) {
+            par.set_text(&gui, &clock_text(app_state.world.time, clock.time_scale()));
+        }
     }
 }
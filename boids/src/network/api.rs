@@ -0,0 +1,245 @@
+//! Remote control of a running simulation through a local HTTP server.
+//!
+//! Every request and response body is JSON:
+//!
+//! | method     | path          | body                       | effect                                  |
+//! |------------|---------------|----------------------------|-----------------------------------------|
+//! | GET        | `/parameters` |                            | the parameters, named as in the presets |
+//! | PUT, POST  | `/parameters` | `{"name": value, ...}`     | updates the given parameters at once    |
+//! | POST       | `/pause`      |                            | stops computing the steps               |
+//! | POST       | `/resume`     |                            | computes the steps again                |
+//! | POST       | `/reset`      |                            | restarts the simulation, see `World::reset` |
+//! | POST       | `/spawn`      | `{"count": n}`             | adds `n` randomly placed boids          |
+//! | GET        | `/metrics`    |                            | time, number of boids and flock metrics |
+//! | GET        | `/svg`        |                            | the current frame as an SVG image       |
+//!
+//! `/svg` accepts the query parameters `width`, `height` (800, 600), `arrows`, `boundary`
+//! (false, true) and `colour` (`speed`, `heading`, `neighbours` or `cluster`), as `boids svg`.
+//!
+//! `/spawn` answers a `400` when the flock would have more than [`MAX_SPAWN_COUNT`] boids.
+//!
+//! The parameter updates are validated as a whole: when one value is rejected, none is
+//! applied and the answer is a `400` with `{"error": message}`.
+//!
+//! The requests are served by [`ApiServer::process`], between two steps of the
+//! simulation, so a paused simulation still answers while a busy one answers late.
+
+use std::convert::TryFrom;
+
+use serde_json::{json, Map, Value};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::data::metrics::FlockMetrics;
+use crate::data::preset::{preset_values, set_preset_value};
+use crate::data::world::{World, MAX_SPAWN_COUNT};
+use crate::network::RemoteChanges;
+use crate::render::svg::{world_to_svg, SvgOptions};
+use crate::render::view::View;
+
+const JSON: &str = "application/json";
+const SVG: &str = "image/svg+xml";
+
+/// HTTP server listening on localhost, see the module documentation for the endpoints
+pub struct ApiServer {
+    server: Server,
+}
+
+impl ApiServer {
+    /// Starts listening on `127.0.0.1:port`
+    pub fn start(port: u16) -> Result<Self, String> {
+        let server = Server::http(("127.0.0.1", port))
+            .map_err(|e| format!("Cannot listen on port {}: {}", port, e))?;
+        Ok(ApiServer { server })
+    }
+
+    /// Serves the pending requests without waiting for new ones.
+    /// `paused` is the pause state of the caller, changed by `/pause` and `/resume`.
+    pub fn process(&self, world: &mut World, paused: &mut bool) -> RemoteChanges {
+        let mut changes = RemoteChanges::default();
+        loop {
+            match self.server.try_recv() {
+                Ok(Some(request)) => serve(request, world, paused, &mut changes),
+                Ok(None) => break,
+                Err(error) => {
+                    eprintln!("api: {}", error);
+                    break;
+                }
+            }
+        }
+        changes
+    }
+}
+
+fn serve(mut request: Request, world: &mut World, paused: &mut bool, changes: &mut RemoteChanges) {
+    let mut body = String::new();
+    let (status, content_type, answer) = match request.as_reader().read_to_string(&mut body) {
+        Err(error) => (400, JSON, error_value(&format!("cannot read the body: {}", error)).to_string()),
+        Ok(_) => {
+            let url = request.url().to_string();
+            let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+            if *request.method() == Method::Get && path == "/svg" {
+                match svg_answer(world, query) {
+                    Ok(svg) => (200, SVG, svg),
+                    Err(error) => (400, JSON, error_value(&error).to_string()),
+                }
+            } else {
+                let (status, value) = handle(request.method(), path, &body, world, paused, changes);
+                (status, JSON, value.to_string())
+            }
+        }
+    };
+
+    let content_type = Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).unwrap();
+    let response = Response::from_string(answer)
+        .with_status_code(status)
+        .with_header(content_type);
+    if let Err(error) = request.respond(response) {
+        eprintln!("api: cannot answer: {}", error);
+    }
+}
+
+fn handle(
+    method: &Method,
+    path: &str,
+    body: &str,
+    world: &mut World,
+    paused: &mut bool,
+    changes: &mut RemoteChanges,
+) -> (u16, Value) {
+    match (method, path) {
+        (Method::Get, "/parameters") => (200, parameters_value(world)),
+        (Method::Put, "/parameters") | (Method::Post, "/parameters") => {
+            match update_parameters(world, body) {
+                Ok(()) => {
+                    changes.parameters = true;
+                    (200, parameters_value(world))
+                }
+                Err(error) => (400, error_value(&error)),
+            }
+        }
+        (Method::Post, "/pause") | (Method::Post, "/resume") => {
+            *paused = path == "/pause";
+            (200, json!({ "paused": *paused }))
+        }
+        (Method::Post, "/reset") => {
+            world.reset();
+            changes.flock = true;
+            (200, metrics_value(world, *paused))
+        }
+        (Method::Post, "/spawn") => match spawn_count(body, world.spawn_capacity()) {
+            Ok(count) => {
+                world.spawn_boids(count);
+                changes.flock = true;
+                (200, metrics_value(world, *paused))
+            }
+            Err(error) => (400, error_value(&error)),
+        },
+        (Method::Get, "/metrics") => (200, metrics_value(world, *paused)),
+        (_, "/parameters" | "/pause" | "/resume" | "/reset" | "/spawn" | "/metrics" | "/svg") => {
+            (405, error_value(&format!("{} is not allowed on {}", method, path)))
+        }
+        _ => (404, error_value(&format!("unknown path {}", path))),
+    }
+}
+
+fn svg_answer(world: &World, query: &str) -> Result<String, String> {
+    let mut width = 800;
+    let mut height = 600;
+    let mut options = SvgOptions { boundary: true, ..SvgOptions::default() };
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let invalid = || format!("invalid value '{}' for '{}'", value, name);
+        match name {
+            "width" => width = value.parse().map_err(|_| invalid())?,
+            "height" => height = value.parse().map_err(|_| invalid())?,
+            "arrows" => options.arrows = value.parse().map_err(|_| invalid())?,
+            "boundary" => options.boundary = value.parse().map_err(|_| invalid())?,
+            "colour" => options.metric = Some(value.parse()?),
+            _ => return Err(format!("unknown query parameter '{}'", name)),
+        }
+    }
+    if width == 0 || height == 0 {
+        return Err("the width and the height must be positive".to_string());
+    }
+    Ok(world_to_svg(world, &View::fit(width, height, world.playfield_size), &options))
+}
+
+fn error_value(message: &str) -> Value {
+    json!({ "error": message })
+}
+
+/// The numbers and booleans of the presets are sent as JSON numbers and booleans
+fn parameters_value(world: &World) -> Value {
+    let fields: Map<String, Value> = preset_values(&world.parameters)
+        .into_iter()
+        .map(|(name, value)| {
+            let value = if let Ok(boolean) = value.parse::<bool>() {
+                Value::Bool(boolean)
+            } else if let Some(number) = value.parse::<f64>().ok().and_then(serde_json::Number::from_f64) {
+                Value::Number(number)
+            } else {
+                Value::String(value)
+            };
+            (name.to_string(), value)
+        })
+        .collect();
+    Value::Object(fields)
+}
+
+fn update_parameters(world: &mut World, body: &str) -> Result<(), String> {
+    let fields = match serde_json::from_str::<Value>(body) {
+        Ok(Value::Object(fields)) => fields,
+        Ok(_) => return Err("expected a JSON object".to_string()),
+        Err(error) => return Err(format!("invalid JSON: {}", error)),
+    };
+
+    // the values are set on a copy, kept only when all of them are accepted
+    let mut result = Ok(());
+    world
+        .update_parameters(|builder| {
+            let mut updated = builder.clone();
+            result = fields.iter().try_for_each(|(name, value)| {
+                let value = match value {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                };
+                set_preset_value(&mut updated, name, &value)
+            });
+            if result.is_ok() {
+                *builder = updated;
+            }
+        })
+        .map_err(|e| e.to_string())?;
+    result
+}
+
+/// the count of the body, which must not exceed `capacity`
+fn spawn_count(body: &str, capacity: usize) -> Result<usize, String> {
+    let value: Value = serde_json::from_str(body).map_err(|e| format!("invalid JSON: {}", e))?;
+    value
+        .get("count")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| "expected {\"count\": n} with n a non-negative integer".to_string())
+        .and_then(|count| match usize::try_from(count) {
+            Ok(count) if count <= capacity => Ok(count),
+            _ => Err(format!(
+                "cannot spawn {} boids, the flock cannot have more than {} boids and {} can still be spawned",
+                count, MAX_SPAWN_COUNT, capacity
+            )),
+        })
+}
+
+fn metrics_value(world: &World, paused: bool) -> Value {
+    let metrics = FlockMetrics::compute(&world.current);
+    json!({
+        "time": world.time,
+        "paused": paused,
+        "boids": world.current.len(),
+        "predators": world.predators.len(),
+        "collisions": world.nb_collisions,
+        "catches": world.nb_catches,
+        "polarization": metrics.polarization,
+        "nearest_neighbour_distance": metrics.nearest_neighbour_distance,
+        "milling": metrics.milling,
+    })
+}
@@ -0,0 +1,19 @@
+pub mod api;
+pub mod osc;
+pub mod stream;
+
+/// What the requests received by a remote control changed, for the caller
+/// to update what depends on it
+#[derive(Copy, Clone, Default)]
+pub struct RemoteChanges {
+    pub parameters: bool,
+    /// the boids were reset, added or removed
+    pub flock: bool,
+}
+
+impl RemoteChanges {
+    pub fn merge(&mut self, other: RemoteChanges) {
+        self.parameters |= other.parameters;
+        self.flock |= other.flock;
+    }
+}
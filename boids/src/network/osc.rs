@@ -0,0 +1,333 @@
+//! Control of a running simulation by Open Sound Control messages received over UDP,
+//! typically sent by a lighting desk or a controller.
+//!
+//! The OSC addresses are bound to parameters and actions by a map file, one binding
+//! per line, `#` starting a comment:
+//!
+//! ```text
+//! # address            target             [min max]
+//! /boids/cohesion      cohesion_factor    0 1
+//! /boids/radius        cohesion_radius    0.05 2
+//! /boids/kernel        cohesion_kernel
+//! /boids/reset         reset
+//! /boids/predator      predator
+//! /boids/spawn         spawn
+//! ```
+//!
+//! A parameter target is a field of the presets, set to the first argument of the
+//! message. When `min max` is given, the argument, expected between 0 and 1 as sent
+//! by the faders, is mapped linearly to this range. A numerical argument sets a
+//! boolean field when it is at least 0.5.
+//!
+//! The `reset` and `predator` actions are triggered by a message without argument or
+//! whose first argument is not 0, so that a button sending 1 when pressed and 0 when
+//! released acts once. `predator` releases a predator at the centre of the playfield,
+//! or at `x y` when the message has two arguments. `spawn n` adds `n` boids, the flock
+//! growing up to [`MAX_SPAWN_COUNT`](crate::data::world::MAX_SPAWN_COUNT) boids. The non-finite numbers are rejected.
+//!
+//! The listener only receives the packets sent from the same machine unless it is bound
+//! to another address, e.g. `0.0.0.0` for a controller on the local network.
+//!
+//! Without map file, every field of the presets is bound to `/boids/<field>` with its
+//! raw value and the actions to `/boids/reset`, `/boids/predator` and `/boids/spawn`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, UdpSocket};
+
+use crate::data::parameters::Parameters;
+use crate::data::preset::{preset_value, preset_values, set_preset_value};
+use crate::data::world::World;
+use crate::network::RemoteChanges;
+
+const MAX_PACKET_SIZE: usize = 65536;
+const BUNDLE_TAG: &[u8] = b"#bundle\0";
+
+#[derive(Clone)]
+pub enum OscTarget {
+    /// a field of the presets, the argument being mapped to `range` when given
+    Parameter { name: String, range: Option<(f32, f32)> },
+    Reset,
+    Predator,
+    Spawn,
+}
+
+/// Bindings of the OSC addresses, see the module documentation
+#[derive(Clone)]
+pub struct OscMap {
+    bindings: HashMap<String, OscTarget>,
+}
+
+impl OscMap {
+    /// every preset field bound to `/boids/<field>`, the actions to `/boids/<action>`
+    pub fn default_map() -> Self {
+        let mut bindings: HashMap<String, OscTarget> = preset_values(&Parameters::new())
+            .into_iter()
+            .map(|(name, _)| {
+                let target = OscTarget::Parameter { name: name.to_string(), range: None };
+                (format!("/boids/{}", name), target)
+            })
+            .collect();
+        bindings.insert("/boids/reset".to_string(), OscTarget::Reset);
+        bindings.insert("/boids/predator".to_string(), OscTarget::Predator);
+        bindings.insert("/boids/spawn".to_string(), OscTarget::Spawn);
+        OscMap { bindings }
+    }
+
+    pub fn target(&self, address: &str) -> Option<&OscTarget> {
+        self.bindings.get(address)
+    }
+}
+
+pub fn load_osc_map(path: &str) -> Result<OscMap, String> {
+    let content =
+        fs::read_to_string(path).map_err(|e| format!("Cannot read OSC map '{}': {}", path, e))?;
+    parse_osc_map(&content).map_err(|e| format!("Invalid OSC map '{}': {}", path, e))
+}
+
+pub fn parse_osc_map(content: &str) -> Result<OscMap, String> {
+    let mut bindings = HashMap::new();
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (address, target) = parse_binding(line).map_err(|e| format!("line {}: {}", line_number + 1, e))?;
+        bindings.insert(address, target);
+    }
+    Ok(OscMap { bindings })
+}
+
+fn parse_binding(line: &str) -> Result<(String, OscTarget), String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    if words.len() < 2 || !words[0].starts_with('/') {
+        return Err(format!("expected 'address target [min max]', got '{}'", line));
+    }
+    let target = match (words[1], &words[2..]) {
+        ("reset", []) => OscTarget::Reset,
+        ("predator", []) => OscTarget::Predator,
+        ("spawn", []) => OscTarget::Spawn,
+        (name, arguments) => {
+            let default_value = preset_value(&Parameters::new(), name)
+                .ok_or_else(|| format!("unknown parameter or action '{}'", name))?;
+            let range = match arguments {
+                [] => None,
+                [min, max] if default_value.parse::<f32>().is_ok() => Some((
+                    min.parse().map_err(|_| format!("invalid minimum '{}'", min))?,
+                    max.parse().map_err(|_| format!("invalid maximum '{}'", max))?,
+                )),
+                [_, _] => return Err(format!("'{}' is not numerical, it cannot have a range", name)),
+                _ => return Err(format!("expected 'min max' after '{}'", name)),
+            };
+            OscTarget::Parameter { name: name.to_string(), range }
+        }
+    };
+    Ok((words[0].to_string(), target))
+}
+
+/// An argument of a message. The integers and booleans are read as numbers.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OscArgument {
+    Number(f32),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub arguments: Vec<OscArgument>,
+}
+
+/// address the listener is bound to by default, only reachable from the same machine
+pub const DEFAULT_OSC_ADDRESS: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+/// UDP socket receiving the OSC packets
+pub struct OscListener {
+    socket: UdpSocket,
+    map: OscMap,
+    buffer: Vec<u8>,
+}
+
+impl OscListener {
+    /// Listens on `address:port`
+    pub fn start(address: IpAddr, port: u16, map: OscMap) -> Result<Self, String> {
+        let socket = UdpSocket::bind((address, port))
+            .and_then(|socket| socket.set_nonblocking(true).map(|_| socket))
+            .map_err(|e| format!("Cannot listen on UDP {}:{}: {}", address, port, e))?;
+        Ok(OscListener { socket, map, buffer: vec![0; MAX_PACKET_SIZE] })
+    }
+
+    /// Applies the messages received since the previous call, without waiting for new ones.
+    /// The messages whose address is not bound are ignored.
+    pub fn process(&mut self, world: &mut World) -> RemoteChanges {
+        let mut changes = RemoteChanges::default();
+        loop {
+            let size = match self.socket.recv(&mut self.buffer) {
+                Ok(size) => size,
+                Err(error) if error.kind() == ErrorKind::WouldBlock => break,
+                Err(error) => {
+                    eprintln!("osc: {}", error);
+                    break;
+                }
+            };
+            let messages = match decode_packet(&self.buffer[..size]) {
+                Ok(messages) => messages,
+                Err(error) => {
+                    eprintln!("osc: invalid packet: {}", error);
+                    continue;
+                }
+            };
+            for message in messages {
+                if let Some(target) = self.map.target(&message.address) {
+                    if let Err(error) = apply(target, &message.arguments, world, &mut changes) {
+                        eprintln!("osc: {}: {}", message.address, error);
+                    }
+                }
+            }
+        }
+        changes
+    }
+}
+
+fn apply(
+    target: &OscTarget,
+    arguments: &[OscArgument],
+    world: &mut World,
+    changes: &mut RemoteChanges,
+) -> Result<(), String> {
+    let number = |index: usize| match arguments.get(index) {
+        Some(OscArgument::Number(value)) if value.is_finite() => Ok(*value),
+        Some(OscArgument::Number(value)) => Err(format!("expected a finite number, got {}", value)),
+        Some(OscArgument::Text(text)) => Err(format!("expected a number, got '{}'", text)),
+        None => Err("missing argument".to_string()),
+    };
+    let is_triggered = arguments.is_empty() || number(0).map(|value| value != 0.0).unwrap_or(true);
+
+    match target {
+        OscTarget::Parameter { name, range } => {
+            let value = match arguments.first() {
+                None => return Err("missing argument".to_string()),
+                Some(OscArgument::Text(text)) => text.clone(),
+                Some(OscArgument::Number(_)) => {
+                    let value = number(0)?;
+                    let is_boolean = preset_value(&world.parameters, name)
+                        .map(|current| current.parse::<bool>().is_ok())
+                        .unwrap_or(false);
+                    match range {
+                        _ if is_boolean => (value >= 0.5).to_string(),
+                        Some((min, max)) => (min + value.clamp(0.0, 1.0) * (max - min)).to_string(),
+                        None => value.to_string(),
+                    }
+                }
+            };
+            let mut result = Ok(());
+            world
+                .update_parameters(|builder| result = set_preset_value(builder, name, &value))
+                .map_err(|e| e.to_string())?;
+            result?;
+            changes.parameters = true;
+        }
+        OscTarget::Reset => {
+            if is_triggered {
+                world.reset();
+                changes.flock = true;
+            }
+        }
+        OscTarget::Predator => {
+            if arguments.len() >= 2 {
+                world.release_predator(number(0)?, number(1)?);
+            } else if is_triggered {
+                world.release_predator(0.0, 0.0);
+            }
+        }
+        OscTarget::Spawn => {
+            let count = number(0)?;
+            if count >= 1.0 {
+                world.spawn_boids((count as usize).min(world.spawn_capacity()));
+                changes.flock = true;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Decodes a message or a bundle, the messages of nested bundles being flattened.
+/// The time tags are ignored, the messages are applied as soon as received.
+pub fn decode_packet(data: &[u8]) -> Result<Vec<OscMessage>, String> {
+    let mut messages = Vec::new();
+    decode_element(data, &mut messages)?;
+    Ok(messages)
+}
+
+fn decode_element(data: &[u8], messages: &mut Vec<OscMessage>) -> Result<(), String> {
+    if !data.starts_with(BUNDLE_TAG) {
+        messages.push(decode_message(data)?);
+        return Ok(());
+    }
+    // tag and time tag
+    let mut offset = BUNDLE_TAG.len() + 8;
+    while offset < data.len() {
+        let size = read_i32(data, &mut offset)?;
+        if size < 0 {
+            return Err(format!("invalid element size {}", size));
+        }
+        let end = offset + size as usize;
+        let element = data.get(offset..end).ok_or("truncated bundle")?;
+        decode_element(element, messages)?;
+        offset = end;
+    }
+    Ok(())
+}
+
+fn decode_message(data: &[u8]) -> Result<OscMessage, String> {
+    let mut offset = 0;
+    let address = read_string(data, &mut offset)?;
+    if !address.starts_with('/') {
+        return Err(format!("invalid address '{}'", address));
+    }
+    let mut arguments = Vec::new();
+    if offset >= data.len() {
+        // old implementations omit the type tags of the messages without argument
+        return Ok(OscMessage { address, arguments });
+    }
+
+    let type_tags = read_string(data, &mut offset)?;
+    let type_tags = type_tags.strip_prefix(',').ok_or("missing type tags")?;
+    for tag in type_tags.chars() {
+        let argument = match tag {
+            'f' => OscArgument::Number(f32::from_bits(read_i32(data, &mut offset)? as u32)),
+            'i' => OscArgument::Number(read_i32(data, &mut offset)? as f32),
+            'd' => OscArgument::Number(f64::from_bits(read_i64(data, &mut offset)? as u64) as f32),
+            'h' => OscArgument::Number(read_i64(data, &mut offset)? as f32),
+            'T' => OscArgument::Number(1.0),
+            'F' => OscArgument::Number(0.0),
+            's' | 'S' => OscArgument::Text(read_string(data, &mut offset)?),
+            'N' | 'I' => continue,
+            _ => return Err(format!("unsupported argument type '{}' in {}", tag, address)),
+        };
+        arguments.push(argument);
+    }
+    Ok(OscMessage { address, arguments })
+}
+
+/// null terminated string padded to a multiple of 4 bytes
+fn read_string(data: &[u8], offset: &mut usize) -> Result<String, String> {
+    let remaining = data.get(*offset..).ok_or("truncated message")?;
+    let length = remaining.iter().position(|byte| *byte == 0).ok_or("unterminated string")?;
+    let text = std::str::from_utf8(&remaining[..length]).map_err(|_| "invalid UTF-8 string")?;
+    *offset += (length + 4) & !3;
+    Ok(text.to_string())
+}
+
+fn read_i32(data: &[u8], offset: &mut usize) -> Result<i32, String> {
+    let bytes = data.get(*offset..*offset + 4).ok_or("truncated message")?;
+    *offset += 4;
+    Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_i64(data: &[u8], offset: &mut usize) -> Result<i64, String> {
+    let high = read_i32(data, offset)? as u32 as i64;
+    let low = read_i32(data, offset)? as u32 as i64;
+    Ok((high << 32) | low)
+}
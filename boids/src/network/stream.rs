@@ -0,0 +1,221 @@
+//! Streaming of the simulation state to WebSocket clients.
+//!
+//! Every `decimation` steps the server sends one binary message per client.
+//! All the values are little endian:
+//!
+//! | offset | type    | content                                   |
+//! |--------|---------|-------------------------------------------|
+//! | 0      | [u8; 4] | magic `BOID`                              |
+//! | 4      | u32     | framing version, currently 1              |
+//! | 8      | u64     | step number                               |
+//! | 16     | f32     | simulation time in seconds                |
+//! | 20     | f32     | half size of the playfield                |
+//! | 24     | f32     | polarization                              |
+//! | 28     | f32     | nearest neighbour distance                |
+//! | 32     | f32     | milling                                   |
+//! | 36     | u32     | number of boids `n`                       |
+//! | 40     | f32 × 4n| `x`, `y`, `vx`, `vy` of each boid in turn |
+//!
+//! A browser can decode it with a `DataView` over the received `ArrayBuffer`.
+//! `boids stream-client` is a minimal client printing a summary of each frame.
+
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use tungstenite::{Message, WebSocket};
+
+use crate::data::flock::Flock;
+use crate::data::metrics::FlockMetrics;
+use crate::data::world::World;
+
+pub const FRAME_MAGIC: [u8; 4] = *b"BOID";
+pub const FRAME_VERSION: u32 = 1;
+const HEADER_SIZE: usize = 40;
+const BOID_SIZE: usize = 16;
+/// frames waiting to be sent, the newer frames are dropped when the clients are too slow
+const FRAME_QUEUE_SIZE: usize = 2;
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+/// a client not completing its handshake within this duration is dropped
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// the clients waiting for the next frame, and the number of connected clients, which
+/// the simulation reads without waiting for the frames being sent
+#[derive(Clone, Default)]
+struct Clients {
+    waiting: Arc<Mutex<Vec<WebSocket<TcpStream>>>>,
+    count: Arc<AtomicUsize>,
+}
+
+/// State of the world to send, the frame being encoded by the sending thread
+struct Snapshot {
+    step: u64,
+    time: f32,
+    playfield_size: f32,
+    flock: Flock,
+}
+
+/// WebSocket server listening on localhost. The accepted clients receive the frames
+/// published with [`StreamServer::publish`], see the module documentation for the framing.
+pub struct StreamServer {
+    decimation: usize,
+    nb_steps: u64,
+    clients: Clients,
+    frames: SyncSender<Snapshot>,
+}
+
+impl StreamServer {
+    /// Starts listening on `127.0.0.1:port`. One frame is sent every `decimation` steps.
+    pub fn start(port: u16, decimation: usize) -> Result<Self, String> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .map_err(|e| format!("Cannot listen on port {}: {}", port, e))?;
+        let clients = Clients::default();
+        let (frames, receiver) = sync_channel(FRAME_QUEUE_SIZE);
+
+        let accepted_clients = clients.clone();
+        thread::spawn(move || accept_clients(listener, accepted_clients));
+        let sent_clients = clients.clone();
+        thread::spawn(move || send_frames(receiver, sent_clients));
+
+        Ok(StreamServer {
+            decimation: decimation.max(1),
+            nb_steps: 0,
+            clients,
+            frames,
+        })
+    }
+
+    pub fn nb_clients(&self) -> usize {
+        self.clients.count.load(Ordering::Relaxed)
+    }
+
+    /// To be called after each step. Sends the state of the world every `decimation` calls.
+    pub fn publish(&mut self, world: &World) {
+        let step = self.nb_steps;
+        self.nb_steps += 1;
+        if !step.is_multiple_of(self.decimation as u64) || self.nb_clients() == 0 {
+            return;
+        }
+        let snapshot = Snapshot {
+            step,
+            time: world.time,
+            playfield_size: world.playfield_size,
+            flock: world.current.clone(),
+        };
+        match self.frames.try_send(snapshot) {
+            Ok(()) | Err(TrySendError::Full(_)) => {}
+            Err(TrySendError::Disconnected(_)) => eprintln!("stream: the sending thread stopped"),
+        }
+    }
+}
+
+fn accept_clients(listener: TcpListener, clients: Clients) {
+    for stream in listener.incoming().flatten() {
+        if stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err()
+            || stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT)).is_err()
+        {
+            continue;
+        }
+        match tungstenite::accept(stream) {
+            Ok(websocket) => {
+                if let Ok(mut waiting) = clients.waiting.lock() {
+                    waiting.push(websocket);
+                    clients.count.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            Err(error) => eprintln!("stream: handshake failed: {}", error),
+        }
+    }
+}
+
+/// Encodes the snapshots and sends them, out of the lock so that the clients connecting
+/// meanwhile are not kept waiting
+fn send_frames(snapshots: Receiver<Snapshot>, clients: Clients) {
+    for snapshot in snapshots {
+        let frame = encode_frame(snapshot.step, snapshot.time, snapshot.playfield_size, &snapshot.flock);
+        let mut sent = match clients.waiting.lock() {
+            Ok(mut waiting) => std::mem::take(&mut *waiting),
+            Err(_) => return,
+        };
+        let nb_sent = sent.len();
+        sent.retain_mut(|client| client.send(Message::Binary(frame.clone())).is_ok());
+        if let Ok(mut waiting) = clients.waiting.lock() {
+            clients.count.fetch_sub(nb_sent - sent.len(), Ordering::Relaxed);
+            sent.append(&mut waiting);
+            *waiting = sent;
+        }
+    }
+}
+
+pub fn encode_frame(step: u64, time: f32, playfield_size: f32, flock: &Flock) -> Vec<u8> {
+    let metrics = FlockMetrics::compute(flock);
+    let mut frame = Vec::with_capacity(HEADER_SIZE + BOID_SIZE * flock.len());
+    frame.extend_from_slice(&FRAME_MAGIC);
+    frame.extend_from_slice(&FRAME_VERSION.to_le_bytes());
+    frame.extend_from_slice(&step.to_le_bytes());
+    for value in [
+        time,
+        playfield_size,
+        metrics.polarization,
+        metrics.nearest_neighbour_distance,
+        metrics.milling,
+    ] {
+        frame.extend_from_slice(&value.to_le_bytes());
+    }
+    frame.extend_from_slice(&(flock.len() as u32).to_le_bytes());
+    for i in 0..flock.len() {
+        for value in [flock.x[i], flock.y[i], flock.vx[i], flock.vy[i]] {
+            frame.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    frame
+}
+
+/// A frame decoded by [`decode_frame`]
+pub struct Frame {
+    pub step: u64,
+    pub time: f32,
+    pub playfield_size: f32,
+    pub metrics: FlockMetrics,
+    /// `[x, y, vx, vy]` of each boid
+    pub boids: Vec<[f32; 4]>,
+}
+
+pub fn decode_frame(data: &[u8]) -> Result<Frame, String> {
+    if data.len() < HEADER_SIZE || data[0..4] != FRAME_MAGIC {
+        return Err("not a boids frame".to_string());
+    }
+    let u32_at = |offset: usize| u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]]);
+    let f32_at = |offset: usize| f32::from_bits(u32_at(offset));
+
+    let version = u32_at(4);
+    if version != FRAME_VERSION {
+        return Err(format!("unsupported framing version {}", version));
+    }
+    let nb_boids = u32_at(36) as usize;
+    if data.len() != HEADER_SIZE + BOID_SIZE * nb_boids {
+        return Err(format!("truncated frame of {} bytes for {} boids", data.len(), nb_boids));
+    }
+
+    let mut step = [0u8; 8];
+    step.copy_from_slice(&data[8..16]);
+    Ok(Frame {
+        step: u64::from_le_bytes(step),
+        time: f32_at(16),
+        playfield_size: f32_at(20),
+        metrics: FlockMetrics {
+            polarization: f32_at(24),
+            nearest_neighbour_distance: f32_at(28),
+            milling: f32_at(32),
+        },
+        boids: (0..nb_boids)
+            .map(|i| {
+                let offset = HEADER_SIZE + BOID_SIZE * i;
+                [f32_at(offset), f32_at(offset + 4), f32_at(offset + 8), f32_at(offset + 12)]
+            })
+            .collect(),
+    })
+}
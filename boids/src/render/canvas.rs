@@ -0,0 +1,170 @@
+pub type Rgb = [u8; 3];
+
+/// number of samples per pixel along each axis, the edges are antialiased
+/// with the fraction of the samples covered by the shape
+const SUBSAMPLES: usize = 4;
+
+/// RGB image the shapes are rasterised into, in pixel coordinates
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, background: Rgb) -> Self {
+        let mut canvas = Canvas {
+            width,
+            height,
+            pixels: vec![0; 3 * width as usize * height as usize],
+        };
+        canvas.clear(background);
+        canvas
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// rows of `width` RGB pixels, from the top of the image
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn clear(&mut self, background: Rgb) {
+        for pixel in self.pixels.chunks_exact_mut(3) {
+            pixel.copy_from_slice(&background);
+        }
+    }
+
+    pub fn fill_triangle(&mut self, points: [(f32, f32); 3], color: Rgb) {
+        let [(x0, y0), (x1, y1), (x2, y2)] = points;
+        let area = (x1 - x0) * (y2 - y0) - (y1 - y0) * (x2 - x0);
+        if area == 0.0 {
+            return;
+        }
+        // the sign of the area makes the test independent of the winding
+        let edge = move |xa: f32, ya: f32, xb: f32, yb: f32, x: f32, y: f32| {
+            ((xb - xa) * (y - ya) - (yb - ya) * (x - xa)) * area >= 0.0
+        };
+        self.fill_shape(
+            (x0.min(x1).min(x2), y0.min(y1).min(y2)),
+            (x0.max(x1).max(x2), y0.max(y1).max(y2)),
+            color,
+            1.0,
+            |x, y| edge(x0, y0, x1, y1, x, y) && edge(x1, y1, x2, y2, x, y) && edge(x2, y2, x0, y0, x, y),
+        );
+    }
+
+    pub fn fill_circle(&mut self, center: (f32, f32), radius: f32, color: Rgb) {
+        let (cx, cy) = center;
+        let radius2 = radius * radius;
+        self.fill_shape((cx - radius, cy - radius), (cx + radius, cy + radius), color, 1.0, |x, y| {
+            (x - cx) * (x - cx) + (y - cy) * (y - cy) <= radius2
+        });
+    }
+
+    /// Draws the outline of a circle, blended with the given opacity
+    pub fn draw_circle(&mut self, center: (f32, f32), radius: f32, width: f32, color: Rgb, opacity: f32) {
+        let (cx, cy) = center;
+        let outer = radius + width * 0.5;
+        let inner = (radius - width * 0.5).max(0.0);
+        self.fill_shape((cx - outer, cy - outer), (cx + outer, cy + outer), color, opacity, |x, y| {
+            let distance2 = (x - cx) * (x - cx) + (y - cy) * (y - cy);
+            distance2 <= outer * outer && distance2 >= inner * inner
+        });
+    }
+
+    /// Fills the circular sector of the given radius spanning `half_angle` radians on both sides
+    /// of the direction `angle`, blended with the given opacity
+    pub fn fill_sector(&mut self, center: (f32, f32), radius: f32, angle: f32, half_angle: f32, color: Rgb, opacity: f32) {
+        let (cx, cy) = center;
+        let (dx, dy) = (angle.cos(), angle.sin());
+        let cos_half_angle = half_angle.cos();
+        self.fill_shape((cx - radius, cy - radius), (cx + radius, cy + radius), color, opacity, |x, y| {
+            let (px, py) = (x - cx, y - cy);
+            let distance = (px * px + py * py).sqrt();
+            distance <= radius && px * dx + py * dy >= distance * cos_half_angle
+        });
+    }
+
+    /// Fills the axis aligned rectangle of opposite corners `from` and `to`, blended with the given opacity
+    pub fn fill_rectangle(&mut self, from: (f32, f32), to: (f32, f32), color: Rgb, opacity: f32) {
+        let min = (from.0.min(to.0), from.1.min(to.1));
+        let max = (from.0.max(to.0), from.1.max(to.1));
+        self.fill_shape(min, max, color, opacity, |x, y| x >= min.0 && x <= max.0 && y >= min.1 && y <= max.1);
+    }
+
+    /// Draws the segment from `from` to `to` with round ends, blended with the given opacity
+    pub fn draw_line(&mut self, from: (f32, f32), to: (f32, f32), width: f32, color: Rgb, opacity: f32) {
+        let ((x0, y0), (x1, y1)) = (from, to);
+        let half_width = width * 0.5;
+        let (dx, dy) = (x1 - x0, y1 - y0);
+        let length2 = (dx * dx + dy * dy).max(1e-12);
+        self.fill_shape(
+            (x0.min(x1) - half_width, y0.min(y1) - half_width),
+            (x0.max(x1) + half_width, y0.max(y1) + half_width),
+            color,
+            opacity,
+            |x, y| {
+                let t = (((x - x0) * dx + (y - y0) * dy) / length2).clamp(0.0, 1.0);
+                let (ex, ey) = (x - x0 - t * dx, y - y0 - t * dy);
+                ex * ex + ey * ey <= half_width * half_width
+            },
+        );
+    }
+
+    /// Fills the pixels of the bounding box `min`-`max` covered by the shape whose interior
+    /// is given by `inside`
+    fn fill_shape(
+        &mut self,
+        min: (f32, f32),
+        max: (f32, f32),
+        color: Rgb,
+        opacity: f32,
+        inside: impl Fn(f32, f32) -> bool,
+    ) {
+        let ((min_x, min_y), (max_x, max_y)) = (min, max);
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        if max_x < 0.0 || max_y < 0.0 || min_x >= self.width as f32 || min_y >= self.height as f32 {
+            return;
+        }
+        let first_column = min_x.max(0.0) as u32;
+        let last_column = (max_x.ceil() as u32).min(self.width - 1);
+        let first_row = min_y.max(0.0) as u32;
+        let last_row = (max_y.ceil() as u32).min(self.height - 1);
+        let step = 1.0 / SUBSAMPLES as f32;
+
+        for row in first_row..=last_row {
+            for column in first_column..=last_column {
+                let mut nb_covered = 0;
+                for i in 0..SUBSAMPLES {
+                    for j in 0..SUBSAMPLES {
+                        let x = column as f32 + (i as f32 + 0.5) * step;
+                        let y = row as f32 + (j as f32 + 0.5) * step;
+                        if inside(x, y) {
+                            nb_covered += 1;
+                        }
+                    }
+                }
+                if nb_covered > 0 {
+                    let coverage = nb_covered as f32 / (SUBSAMPLES * SUBSAMPLES) as f32;
+                    self.blend(column, row, color, coverage * opacity);
+                }
+            }
+        }
+    }
+
+    fn blend(&mut self, column: u32, row: u32, color: Rgb, coverage: f32) {
+        let offset = 3 * (row as usize * self.width as usize + column as usize);
+        for (channel, value) in self.pixels[offset..offset + 3].iter_mut().zip(color.iter()) {
+            *channel = (*channel as f32 + (*value as f32 - *channel as f32) * coverage).round() as u8;
+        }
+    }
+}
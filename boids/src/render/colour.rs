@@ -0,0 +1,91 @@
+use std::f32::consts::PI;
+
+use crate::data::metrics::BoidMetric;
+use crate::data::parameters::Parameters;
+use crate::data::world::World;
+use crate::render::canvas::Rgb;
+use crate::render::scene::BIRD_COLOR;
+
+/// colours of the sequential scale, from the smallest to the largest values (viridis)
+const SCALE: [Rgb; 5] = [[68, 1, 84], [59, 82, 139], [33, 145, 140], [94, 201, 98], [253, 231, 37]];
+
+/// colours of the largest clusters, from the largest
+pub const CLUSTER_COLORS: [Rgb; 8] = [
+    [31, 119, 180],
+    [255, 127, 14],
+    [44, 160, 44],
+    [214, 39, 40],
+    [148, 103, 189],
+    [140, 86, 75],
+    [227, 119, 194],
+    [188, 189, 34],
+];
+/// colour of the isolated boids and of the clusters without colour of their own
+pub const OTHER_CLUSTER_COLOR: Rgb = [170, 170, 170];
+
+/// Range the colours of `metric` are spread over: the range of the speeds allowed by the
+/// parameters, the whole circle for the heading, from 0 to the largest value for the number
+/// of neighbours and the ranks of the clusters with a colour of their own
+pub fn metric_range(metric: BoidMetric, values: &[f32], parameters: &Parameters) -> (f32, f32) {
+    match metric {
+        BoidMetric::Speed => (parameters.min_bird_speed(), parameters.max_bird_speed()),
+        BoidMetric::Heading => (-PI, PI),
+        BoidMetric::Neighbours => (0.0, values.iter().cloned().fold(1.0, f32::max)),
+        BoidMetric::Cluster => (0.0, (CLUSTER_COLORS.len() - 1) as f32),
+    }
+}
+
+/// Colour of `value`: a hue for the heading, a colour per cluster for the clusters,
+/// a position on a sequential scale over `range` otherwise
+pub fn metric_colour(metric: BoidMetric, value: f32, range: (f32, f32)) -> Rgb {
+    let (min, max) = range;
+    let t = if max > min { ((value - min) / (max - min)).clamp(0.0, 1.0) } else { 0.5 };
+    match metric {
+        BoidMetric::Heading => hue(t),
+        BoidMetric::Cluster => {
+            if value < 0.0 {
+                OTHER_CLUSTER_COLOR
+            } else {
+                CLUSTER_COLORS.get(value as usize).copied().unwrap_or(OTHER_CLUSTER_COLOR)
+            }
+        }
+        _ => scale_colour(t),
+    }
+}
+
+/// Colour of each boid of the world, black without metric, and the range the colours
+/// are spread over. The neighbours and the clusters are computed with the cohesion radius.
+pub fn boid_colours(world: &World, metric: Option<BoidMetric>) -> (Vec<Rgb>, Option<(f32, f32)>) {
+    match metric {
+        None => (vec![BIRD_COLOR; world.current.len()], None),
+        Some(metric) => {
+            let values = metric.compute(&world.current, world.parameters.cohesion().radius());
+            let range = metric_range(metric, &values, &world.parameters);
+            (values.iter().map(|value| metric_colour(metric, *value, range)).collect(), Some(range))
+        }
+    }
+}
+
+/// colour of `t` in `[0, 1]` on the sequential scale
+pub fn scale_colour(t: f32) -> Rgb {
+    let position = t * (SCALE.len() - 1) as f32;
+    let index = (position as usize).min(SCALE.len() - 2);
+    let fraction = position - index as f32;
+    let (from, to) = (SCALE[index], SCALE[index + 1]);
+    [0, 1, 2].map(|c| (from[c] as f32 + (to[c] as f32 - from[c] as f32) * fraction).round() as u8)
+}
+
+/// fully saturated colour of hue `t` in `[0, 1]`
+fn hue(t: f32) -> Rgb {
+    let h = (t * 6.0) % 6.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+    let (r, g, b) = match h as usize {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    [(r * 220.0) as u8, (g * 220.0) as u8, (b * 220.0) as u8]
+}
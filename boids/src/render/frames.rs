@@ -0,0 +1,84 @@
+use std::fs::File;
+use std::io::BufWriter;
+
+use gif::{Encoder, Frame, Repeat};
+
+use crate::render::canvas::Canvas;
+
+/// speed of the colour quantisation of the GIF frames, from 1 (best) to 30 (fastest)
+const GIF_QUANTISATION_SPEED: i32 = 10;
+
+/// Destination of the rendered frames: a PNG file per frame or an animated GIF
+pub enum FrameWriter {
+    Png { pattern: String, nb_frames: usize },
+    Gif { encoder: Encoder<BufWriter<File>>, delay: u16 },
+}
+
+impl FrameWriter {
+    /// A `path` ending with `.gif` gives an animation showing a frame every `frame_interval`
+    /// seconds. Any other path gives a PNG per frame, `{}` in the path being replaced by the
+    /// frame number, or the number being added before the extension when there is no `{}`.
+    pub fn create(path: &str, width: u32, height: u32, frame_interval: f32) -> Result<Self, String> {
+        if !path.to_lowercase().ends_with(".gif") {
+            let pattern = if path.contains("{}") {
+                path.to_string()
+            } else {
+                // the extension is searched in the file name only, not in the directories
+                let name_start = path.rfind(std::path::is_separator).map_or(0, |separator| separator + 1);
+                match path[name_start..].rfind('.').map(|dot| name_start + dot) {
+                    Some(dot) => format!("{}_{{}}{}", &path[..dot], &path[dot..]),
+                    None => format!("{}_{{}}.png", path),
+                }
+            };
+            return Ok(FrameWriter::Png { pattern, nb_frames: 0 });
+        }
+
+        if width > u16::MAX as u32 || height > u16::MAX as u32 {
+            return Err(format!("A GIF cannot be larger than {} pixels", u16::MAX));
+        }
+        let file = File::create(path).map_err(|e| format!("Cannot create '{}': {}", path, e))?;
+        let mut encoder = Encoder::new(BufWriter::new(file), width as u16, height as u16, &[])
+            .map_err(|e| format!("Cannot write '{}': {}", path, e))?;
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(|e| format!("Cannot write '{}': {}", path, e))?;
+        Ok(FrameWriter::Gif {
+            encoder,
+            // in hundredths of a second
+            delay: (frame_interval * 100.0).round().clamp(1.0, u16::MAX as f32) as u16,
+        })
+    }
+
+    pub fn write(&mut self, canvas: &Canvas) -> Result<(), String> {
+        match self {
+            FrameWriter::Png { pattern, nb_frames } => {
+                let path = pattern.replace("{}", &format!("{:05}", nb_frames));
+                *nb_frames += 1;
+                write_png(&path, canvas)
+            }
+            FrameWriter::Gif { encoder, delay } => {
+                let mut frame = Frame::from_rgb_speed(
+                    canvas.width() as u16,
+                    canvas.height() as u16,
+                    canvas.pixels(),
+                    GIF_QUANTISATION_SPEED,
+                );
+                frame.delay = *delay;
+                encoder
+                    .write_frame(&frame)
+                    .map_err(|e| format!("Cannot write the GIF frame: {}", e))
+            }
+        }
+    }
+}
+
+pub fn write_png(path: &str, canvas: &Canvas) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Cannot create '{}': {}", path, e))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), canvas.width(), canvas.height());
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(canvas.pixels()))
+        .map_err(|e| format!("Cannot write '{}': {}", path, e))
+}
@@ -0,0 +1,63 @@
+use crate::data::density::DensityGrid;
+use crate::data::vector::Vector;
+use crate::render::canvas::{Canvas, Rgb};
+use crate::render::colour::scale_colour;
+use crate::render::frames::write_png;
+use crate::render::scene::BACKGROUND_COLOR;
+use crate::render::view::View;
+
+/// opacity of the densest cells of the overlay, the emptier ones being more transparent
+pub const HEATMAP_OPACITY: f32 = 0.6;
+const PNG_PIXELS_PER_CELL: u32 = 8;
+
+/// Colour and opacity, relative to the one of the densest cell, of a cell holding `value`,
+/// `max` being the value of the densest cell. `None` for the empty cells, left undrawn.
+pub fn cell_colour(value: f32, max: f32) -> Option<(Rgb, f32)> {
+    if value <= 0.0 || max <= 0.0 {
+        return None;
+    }
+    let t = value / max;
+    Some((scale_colour(t), t.sqrt()))
+}
+
+/// Draws the grid over what the canvas already holds, `opacity` being the one of the densest cell
+pub fn draw_density(canvas: &mut Canvas, view: &View, grid: &DensityGrid, opacity: f32) {
+    let max = grid.max();
+    let cell_size = grid.cell_size();
+    for row in 0..grid.resolution() {
+        for column in 0..grid.resolution() {
+            if let Some((colour, relative_opacity)) = cell_colour(grid.value(column, row), max) {
+                let corner = Vector {
+                    x: -grid.half_size() + column as f32 * cell_size,
+                    y: -grid.half_size() + row as f32 * cell_size,
+                };
+                let opposite = Vector { x: corner.x + cell_size, y: corner.y + cell_size };
+                canvas.fill_rectangle(
+                    view.project(&corner),
+                    view.project(&opposite),
+                    colour,
+                    opacity * relative_opacity,
+                );
+            }
+        }
+    }
+}
+
+/// Writes the grid as a PNG image of `pixels_per_cell` x `pixels_per_cell` pixels per cell,
+/// oriented as in the application
+pub fn save_density_png(path: &str, grid: &DensityGrid, pixels_per_cell: u32) -> Result<(), String> {
+    let size = grid.resolution() as u32 * pixels_per_cell.max(1);
+    let mut canvas = Canvas::new(size, size, BACKGROUND_COLOR);
+    let view = View::centered(size, size, Vector::new(), grid.half_size());
+    draw_density(&mut canvas, &view, grid, 1.0);
+    write_png(path, &canvas)
+}
+
+/// Writes the grid as a PNG image when `path` ends with `.png`, as CSV otherwise
+pub fn save_density(path: &str, grid: &DensityGrid) -> Result<(), String> {
+    if path.to_lowercase().ends_with(".png") {
+        save_density_png(path, grid, PNG_PIXELS_PER_CELL)
+    } else {
+        grid.save_csv(path)
+    }
+}
@@ -0,0 +1,10 @@
+//! Software rendering of the world, for the images produced without window
+
+pub mod canvas;
+pub mod colour;
+pub mod frames;
+pub mod heatmap;
+pub mod perception;
+pub mod scene;
+pub mod svg;
+pub mod view;
@@ -0,0 +1,100 @@
+use crate::data::neighbour::IN_SEPARATION_RANGE;
+use crate::data::parameters::{Parameters, Perception};
+use crate::data::steering::Steering;
+use crate::data::vector::Vector;
+use crate::data::world::World;
+use crate::render::canvas::{Canvas, Rgb};
+use crate::render::view::View;
+
+// the colours of the separation, alignment and cohesion rules
+pub const SEPARATION_COLOR: Rgb = [214, 39, 40];
+pub const ALIGNMENT_COLOR: Rgb = [44, 160, 44];
+pub const COHESION_COLOR: Rgb = [31, 119, 180];
+pub const NEIGHBOUR_COLOR: Rgb = [120, 120, 120];
+/// opacity of the dead angle cones
+pub const CONE_OPACITY: f32 = 0.15;
+/// width of the lines relative to the size of the boids
+pub const LINE_WIDTH: f32 = 0.1;
+/// the steering arrows show the change of velocity applied by each rule at each step,
+/// scaled by this factor
+pub const STEERING_ARROW_SCALE: f32 = 2.0;
+/// number of segments of the arcs of the cones
+pub const ARC_SEGMENTS: usize = 24;
+
+/// What is drawn for one steering rule
+pub struct RuleOverlay {
+    pub color: Rgb,
+    /// range of the rule, the separation radius being the safe space of the boid
+    pub radius: f32,
+    /// half the angle of the cone, centred on the heading, the rule perceives nothing in
+    pub dead_half_angle: f32,
+    /// change of velocity applied by the rule, scaled by [`STEERING_ARROW_SCALE`]
+    pub arrow: Vector,
+}
+
+/// the overlays of the separation, alignment and cohesion rules
+pub fn rule_overlays(parameters: &Parameters, steering: &Steering) -> [RuleOverlay; 3] {
+    let overlay = |color: Rgb, perception: &Perception, steering: &Vector, factor: f32| {
+        let mut arrow = *steering;
+        arrow.scale(factor * STEERING_ARROW_SCALE);
+        RuleOverlay {
+            color,
+            radius: perception.radius(),
+            dead_half_angle: dead_half_angle(perception),
+            arrow,
+        }
+    };
+    [
+        overlay(SEPARATION_COLOR, parameters.separation(), &steering.separation, parameters.separation_factor()),
+        overlay(ALIGNMENT_COLOR, parameters.alignment(), &steering.alignment, parameters.alignment_factor()),
+        overlay(COHESION_COLOR, parameters.cohesion(), &steering.cohesion, parameters.cohesion_factor()),
+    ]
+}
+
+/// Half the angle of the dead cone of `perception`, as the neighbour search applies
+/// `cos_max_angle`: a neighbour is perceived when the angle between the heading and
+/// its direction is at least this one
+pub fn dead_half_angle(perception: &Perception) -> f32 {
+    (-perception.cos_max_angle()).clamp(-1.0, 1.0).acos()
+}
+
+/// Points of the arc of radius `radius` around `center` spanning `half_angle` on both sides of `heading`
+pub fn cone_arc(center: &Vector, heading: f32, half_angle: f32, radius: f32) -> Vec<Vector> {
+    (0..=ARC_SEGMENTS)
+        .map(|k| {
+            let angle = heading - half_angle + 2.0 * half_angle * k as f32 / ARC_SEGMENTS as f32;
+            Vector { x: center.x + radius * angle.cos(), y: center.y + radius * angle.sin() }
+        })
+        .collect()
+}
+
+/// Draws what the boid at `index` perceives: the range and the dead cone of each rule, lines
+/// to the perceived neighbours, red for the ones in the safe space, and the steering of each rule
+pub fn draw_perception(canvas: &mut Canvas, view: &View, world: &World, index: usize) {
+    let perception = match world.perception(index) {
+        Some(perception) => perception,
+        None => return,
+    };
+    let boid = world.current.boid(index);
+    let center = view.project(&boid.position);
+    let heading = boid.velocity.y.atan2(boid.velocity.x);
+    let width = world.parameters.bird_size() * LINE_WIDTH * view.zoom;
+    let overlays = rule_overlays(&world.parameters, &perception.steering);
+
+    for rule in overlays.iter() {
+        let radius = rule.radius * view.zoom;
+        canvas.fill_sector(center, radius, heading, rule.dead_half_angle, rule.color, CONE_OPACITY);
+    }
+    for rule in overlays.iter() {
+        canvas.draw_circle(center, rule.radius * view.zoom, width, rule.color, 1.0);
+    }
+    for neighbour in perception.neighbours.iter() {
+        let color = if neighbour.visibility & IN_SEPARATION_RANGE != 0 { SEPARATION_COLOR } else { NEIGHBOUR_COLOR };
+        canvas.draw_line(center, view.project(&world.current.position(neighbour.index)), width, color, 0.8);
+    }
+    for rule in overlays.iter() {
+        let mut end = boid.position;
+        end.add(&rule.arrow);
+        canvas.draw_line(center, view.project(&end), 2.0 * width, rule.color, 1.0);
+    }
+}
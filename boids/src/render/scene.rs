@@ -0,0 +1,82 @@
+use crate::data::boid::Boid;
+use crate::data::density::DensityGrid;
+use crate::data::metrics::BoidMetric;
+use crate::data::trails::Trails;
+use crate::data::world::World;
+use crate::render::canvas::{Canvas, Rgb};
+use crate::render::colour::boid_colours;
+use crate::render::heatmap::{draw_density, HEATMAP_OPACITY};
+use crate::render::perception::draw_perception;
+use crate::render::view::View;
+
+// the colours of the application
+pub const BACKGROUND_COLOR: Rgb = [255, 255, 255];
+pub const BIRD_COLOR: Rgb = [0, 0, 0];
+pub const OBSTACLE_COLOR: Rgb = [130, 130, 130];
+pub const PREDATOR_COLOR: Rgb = [230, 41, 55];
+/// colour of the boid selected in the application
+pub const SELECTED_COLOR: Rgb = [255, 140, 0];
+pub const TRAIL_COLOR: Rgb = [0, 0, 0];
+/// opacity of the newest segment of the trails, the older ones fading to 0
+pub const TRAIL_OPACITY: f32 = 0.5;
+/// width of the trails relative to the size of the boids
+pub const TRAIL_WIDTH: f32 = 0.15;
+
+/// What is drawn with the world when present
+#[derive(Copy, Clone, Default)]
+pub struct Overlays<'a> {
+    pub trails: Option<&'a Trails>,
+    pub density: Option<&'a DensityGrid>,
+    /// colour the boids by this metric instead of black
+    pub metric: Option<BoidMetric>,
+    /// index of the boid whose perception is drawn over the world
+    pub perception: Option<usize>,
+}
+
+/// Draws the world as the application does: density, obstacles, trails, predators, the boids
+/// and then the perception of the selected boid
+pub fn draw_world(canvas: &mut Canvas, view: &View, world: &World, overlays: &Overlays) {
+    canvas.clear(BACKGROUND_COLOR);
+    if let Some(density) = overlays.density {
+        draw_density(canvas, view, density, HEATMAP_OPACITY);
+    }
+    for obstacle in world.obstacles.iter() {
+        canvas.fill_circle(view.project(&obstacle.center), obstacle.radius * view.zoom, OBSTACLE_COLOR);
+    }
+    let bird_size = world.parameters.bird_size();
+    if let Some(trails) = overlays.trails {
+        draw_trails(canvas, view, trails, bird_size * TRAIL_WIDTH * view.zoom);
+    }
+    for predator in world.predators.iter() {
+        draw_bird(canvas, view, predator, bird_size * 2.0, PREDATOR_COLOR);
+    }
+    let (colours, _) = boid_colours(world, overlays.metric);
+    for (boid, colour) in world.current.iter().zip(colours) {
+        draw_bird(canvas, view, &boid, bird_size, colour);
+    }
+    if let Some(index) = overlays.perception {
+        draw_perception(canvas, view, world, index);
+    }
+}
+
+fn draw_bird(canvas: &mut Canvas, view: &View, boid: &Boid, bird_size: f32, color: Rgb) {
+    let [head, left_wing, right_wing] = boid.triangle(bird_size);
+    canvas.fill_triangle(
+        [view.project(&head), view.project(&left_wing), view.project(&right_wing)],
+        color,
+    );
+}
+
+fn draw_trails(canvas: &mut Canvas, view: &View, trails: &Trails, width: f32) {
+    let length = trails.length() as f32;
+    for index in 0..trails.nb_birds() {
+        let points: Vec<(f32, f32)> = trails.trail(index).map(|position| view.project(position)).collect();
+        let nb_segments = points.len().saturating_sub(1) as f32;
+        for (k, segment) in points.windows(2).enumerate() {
+            // the trails of the same length fade the same way, however many positions they hold
+            let age = nb_segments - k as f32;
+            let opacity = TRAIL_OPACITY * (1.0 - age / length);
+            canvas.draw_line(segment[0], segment[1], width, TRAIL_COLOR, opacity);
+        }
+    }
+}
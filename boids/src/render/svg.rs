@@ -0,0 +1,126 @@
+use std::fmt::Write;
+use std::fs;
+
+use crate::data::boid::Boid;
+use crate::data::metrics::BoidMetric;
+use crate::data::vector::Vector;
+use crate::data::world::World;
+use crate::render::canvas::Rgb;
+use crate::render::colour::boid_colours;
+use crate::render::scene::{BACKGROUND_COLOR, OBSTACLE_COLOR, PREDATOR_COLOR};
+use crate::render::view::View;
+
+/// the velocity arrows show the distance travelled in this duration, in seconds
+const ARROW_DURATION: f32 = 0.05;
+const ARROW_COLOR: Rgb = [0, 110, 220];
+const BOUNDARY_COLOR: Rgb = [100, 100, 100];
+
+#[derive(Copy, Clone, Default)]
+pub struct SvgOptions {
+    /// draw the velocity of each boid as an arrow
+    pub arrows: bool,
+    /// draw the rectangle the boids are steered back into
+    pub boundary: bool,
+    /// colour the boids by this metric instead of black
+    pub metric: Option<BoidMetric>,
+}
+
+/// The world as an SVG image of the size of `view`, the boids being drawn
+/// with the same triangles as in the application
+pub fn world_to_svg(world: &World, view: &View, options: &SvgOptions) -> String {
+    let mut svg = String::new();
+    // writing to a String cannot fail
+    let _ = write_svg(&mut svg, world, view, options);
+    svg
+}
+
+pub fn save_svg(path: &str, world: &World, view: &View, options: &SvgOptions) -> Result<(), String> {
+    fs::write(path, world_to_svg(world, view, options)).map_err(|e| format!("Cannot write '{}': {}", path, e))
+}
+
+fn write_svg(svg: &mut String, world: &World, view: &View, options: &SvgOptions) -> std::fmt::Result {
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+        w = view.width,
+        h = view.height
+    )?;
+    if options.arrows {
+        writeln!(
+            svg,
+            "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" refY=\"5\" markerWidth=\"4\" \
+             markerHeight=\"4\" orient=\"auto\"><path d=\"M0,0 L10,5 L0,10 z\" fill=\"{}\"/></marker></defs>",
+            hex(ARROW_COLOR)
+        )?;
+    }
+    writeln!(svg, "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>", hex(BACKGROUND_COLOR))?;
+
+    if options.boundary {
+        let (half_width, half_height) = world.boundary();
+        let (x, y) = view.project(&Vector { x: -half_width, y: -half_height });
+        writeln!(
+            svg,
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"none\" stroke=\"{}\" \
+             stroke-dasharray=\"6,4\"/>",
+            x,
+            y,
+            2.0 * half_width * view.zoom,
+            2.0 * half_height * view.zoom,
+            hex(BOUNDARY_COLOR)
+        )?;
+    }
+
+    for obstacle in world.obstacles.iter() {
+        let (x, y) = view.project(&obstacle.center);
+        writeln!(
+            svg,
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"{}\"/>",
+            x,
+            y,
+            obstacle.radius * view.zoom,
+            hex(OBSTACLE_COLOR)
+        )?;
+    }
+
+    let bird_size = world.parameters.bird_size();
+    for predator in world.predators.iter() {
+        write_bird(svg, view, predator, bird_size * 2.0, PREDATOR_COLOR)?;
+    }
+
+    let (colours, _) = boid_colours(world, options.metric);
+    writeln!(svg, "<g stroke=\"none\">")?;
+    for (boid, colour) in world.current.iter().zip(colours) {
+        write_bird(svg, view, &boid, bird_size, colour)?;
+    }
+    writeln!(svg, "</g>")?;
+
+    if options.arrows {
+        writeln!(svg, "<g stroke=\"{}\" stroke-width=\"1\" marker-end=\"url(#arrow)\">", hex(ARROW_COLOR))?;
+        for boid in world.current.iter() {
+            let mut end = boid.position;
+            end.add_scaled(&boid.velocity, ARROW_DURATION);
+            let (x1, y1) = view.project(&boid.position);
+            let (x2, y2) = view.project(&end);
+            writeln!(svg, "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\"/>", x1, y1, x2, y2)?;
+        }
+        writeln!(svg, "</g>")?;
+    }
+
+    writeln!(svg, "</svg>")
+}
+
+fn write_bird(svg: &mut String, view: &View, boid: &Boid, bird_size: f32, colour: Rgb) -> std::fmt::Result {
+    let points: Vec<String> = boid
+        .triangle(bird_size)
+        .iter()
+        .map(|point| {
+            let (x, y) = view.project(point);
+            format!("{:.2},{:.2}", x, y)
+        })
+        .collect();
+    writeln!(svg, "<polygon points=\"{}\" fill=\"{}\"/>", points.join(" "), hex(colour))
+}
+
+fn hex(colour: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", colour[0], colour[1], colour[2])
+}
@@ -0,0 +1,44 @@
+use crate::data::vector::Vector;
+
+/// Part of the world shown in an image of `width` x `height` pixels.
+/// The axes are oriented as in the application, `y` pointing down.
+#[derive(Copy, Clone)]
+pub struct View {
+    pub width: u32,
+    pub height: u32,
+    /// point of the world shown at the centre of the image
+    pub center: Vector,
+    /// number of pixels per world unit
+    pub zoom: f32,
+}
+
+impl View {
+    /// Centred on the playfield, the playfield filling 80% of the smallest side of the image
+    /// as with the camera of the application
+    pub fn fit(width: u32, height: u32, playfield_size: f32) -> Self {
+        View {
+            width,
+            height,
+            center: Vector::new(),
+            zoom: 0.8 * width.min(height) as f32 / (playfield_size * 2.0),
+        }
+    }
+
+    /// Centred on `center`, showing `view_size` world units above and below it
+    pub fn centered(width: u32, height: u32, center: Vector, view_size: f32) -> Self {
+        View {
+            width,
+            height,
+            center,
+            zoom: height as f32 / (view_size * 2.0),
+        }
+    }
+
+    /// position in the image, in pixels, of the point of the world `position`
+    pub fn project(&self, position: &Vector) -> (f32, f32) {
+        (
+            (position.x - self.center.x) * self.zoom + self.width as f32 * 0.5,
+            (position.y - self.center.y) * self.zoom + self.height as f32 * 0.5,
+        )
+    }
+}